@@ -0,0 +1,71 @@
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+
+// command line of the client
+// every step of the election night can be run on its own, so a failed
+// step can be repeated without doing the ones before it again
+#[derive(Debug, Parser)]
+#[command(name = "client", version, about = "vote42.rs election site client")]
+pub struct Cli {
+    /// local directory holding configs, keys and results [default: ~/.vote42.rs/]
+    #[arg(long, global = true, value_name = "DIR")]
+    pub config_dir: Option<PathBuf>,
+
+    /// never wait for the user to confirm anything
+    #[arg(long, short = 'y', global = true)]
+    pub non_interactive: bool,
+
+    #[command(subcommand)]
+    pub command: Command,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// make the local directory and the config files
+    Init,
+    /// download the vote template and the post-server key from the pre-server
+    Fetch {
+        /// don't connect to the pre-server but import a vote template from
+        /// this path; its signature has to lie next to it as "<TEMPLATE>.sig"
+        #[arg(long, value_name = "TEMPLATE")]
+        offline: Option<PathBuf>,
+
+        /// post-server key to import along with the template in offline mode
+        #[arg(long, value_name = "FILE", requires = "offline")]
+        post_pubring: Option<PathBuf>,
+
+        /// nonce of the election site to import along with the template in
+        /// offline mode, as the pre-server hands it out
+        #[arg(long, value_name = "FILE", requires = "offline")]
+        nonce: Option<PathBuf>,
+    },
+    /// verify the vote template, enter the counts and write the results
+    Enter {
+        /// have every count entered twice by two different operators
+        #[arg(long)]
+        double_entry: bool,
+
+        /// enter a correction of results already submitted, e.g. after a
+        /// recount; it supersedes them on the post-server
+        #[arg(long, value_name = "REASON")]
+        correction: Option<String>,
+    },
+    /// sign the results and encrypt them to the post-server
+    Sign,
+    /// upload the signed and encrypted results to the post-server
+    Submit,
+    /// show which steps are done
+    Status,
+    /// check a vote template and list all problems found in it
+    CheckTemplate {
+        /// vote template to check
+        #[arg(value_name = "TEMPLATE")]
+        path: PathBuf,
+    },
+    /// run init, fetch, enter, sign and submit one after the other
+    Run {
+        /// have every count entered twice by two different operators
+        #[arg(long)]
+        double_entry: bool,
+    },
+}
@@ -0,0 +1,63 @@
+use serde::Deserialize;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::PathBuf;
+
+use crate::error::Error;
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    election_site: String,
+    election_admin: String,
+}
+
+impl Config {
+    // popoulate Config struct from JSON file
+    // takes:
+    //   path to JSON file (PathBuf)
+    // returns:
+    //   config Struct (Config)
+    pub fn create_from_json(json_file_path: PathBuf) -> Result<Self, Error> {
+        println!(
+            "attempting to read JSON template file: {:?}",
+            json_file_path
+        );
+        let file = File::open(json_file_path.clone()).map_err(|e| {
+            Error::Io(format!("failed to open config file {:?}", json_file_path), e)
+        })?;
+
+        let reader = BufReader::new(file);
+
+        let config: Config = serde_json::from_reader(reader).map_err(|e| {
+            Error::Json(format!("failed to parse config file {:?}", json_file_path), e)
+        })?;
+
+        // both end up in every result that is sent
+        if config.election_site.trim().is_empty() || config.election_admin.trim().is_empty() {
+            return Err(Error::Validation(format!(
+                "election_site and election_admin must be set in {:?}",
+                json_file_path
+            )));
+        }
+
+        Ok(config)
+    }
+
+    // get election site name
+    // takes:
+    //   config (Config)
+    // returns:
+    //   name of election site (String)
+    pub fn get_election_site(&self) -> String {
+        self.election_site.clone()
+    }
+
+    // get election admin name
+    // takes:
+    //   config (Config)
+    // returns:
+    //   name of election admin (String)
+    pub fn get_election_admin(&self) -> String {
+        self.election_admin.clone()
+    }
+}
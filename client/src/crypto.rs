@@ -0,0 +1,43 @@
+use rpassword::prompt_password;
+use std::path::PathBuf;
+
+use vote42::crypto;
+
+use crate::error::Error;
+
+// ask the operator for the password of the signing key
+// returns:
+//   password (String)
+fn ask_password() -> Result<String, vote42::error::Error> {
+    println!("to use the signing key you have to enter the password");
+
+    prompt_password("> ")
+        .map_err(|e| vote42::error::Error::Io("failed to read password".to_string(), e))
+}
+
+// sign a file and encrypt it to the post-server
+// the sealed file is written next to the file as "<file>.pgp"
+// takes:
+//   path to secret key file (PathBuf)
+//   path to post-server public key file (PathBuf)
+//   path to file to seal (PathBuf)
+// returns:
+//   path to sealed file (PathBuf)
+pub fn seal_file(
+    secring_path: PathBuf,
+    post_pubring_path: PathBuf,
+    file_path: PathBuf,
+) -> Result<PathBuf, Error> {
+    let keypair = crypto::get_signing_keypair(&secring_path, ask_password)?;
+
+    Ok(crypto::seal_file(keypair, &post_pubring_path, &file_path)?)
+}
+
+// verify a detached signature over a file
+// the signature is expected next to the file as "<file>.sig"
+// takes:
+//   path to public key file of the signer (PathBuf)
+//   path to signed file (PathBuf)
+pub fn verify_file(pubring_path: PathBuf, file_path: PathBuf) -> Result<(), Error> {
+    Ok(crypto::verify_file(&pubring_path, &file_path)?)
+}
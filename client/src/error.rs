@@ -0,0 +1,83 @@
+use std::fmt;
+use std::io;
+
+// everything that can go wrong in the client
+// every class of failure ends the program with its own exit code, so
+// scripts and operators can tell them apart
+#[derive(Debug)]
+pub enum Error {
+    Io(String, io::Error),           // reading or writing local files and the terminal
+    Json(String, serde_json::Error), // parsing or writing JSON files
+    SshAuth(String),                 // connecting and authenticating with a server
+    Sftp(String),                    // transferring files with a server
+    KeyDetection(String),            // finding the local ssh key
+    Validation(String),              // input that is not acceptable
+    Crypto(String),                  // signing, encrypting and verifying with OpenPGP
+}
+
+impl Error {
+    // get the exit code for the class of the error
+    // takes:
+    //   reference to error (&Error)
+    // returns:
+    //   exit code (i32)
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Error::Io(..) => 2,
+            Error::Json(..) => 3,
+            Error::SshAuth(_) => 4,
+            Error::Sftp(_) => 5,
+            Error::KeyDetection(_) => 6,
+            Error::Validation(_) => 7,
+            Error::Crypto(_) => 8,
+        }
+    }
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(context, e) => write!(f, "{}: {}", context, e),
+            Error::Json(context, e) => write!(f, "{}: {}", context, e),
+            Error::SshAuth(msg) => write!(f, "ssh: {}", msg),
+            Error::Sftp(msg) => write!(f, "sftp: {}", msg),
+            Error::KeyDetection(msg) => write!(f, "ssh key: {}", msg),
+            Error::Validation(msg) => write!(f, "invalid input: {}", msg),
+            Error::Crypto(msg) => write!(f, "OpenPGP: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, e) => Some(e),
+            Error::Json(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io("I/O error".to_string(), e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json("JSON error".to_string(), e)
+    }
+}
+
+// errors of the shared code keep their exit codes
+impl From<vote42::error::Error> for Error {
+    fn from(e: vote42::error::Error) -> Self {
+        match e {
+            vote42::error::Error::Io(context, e) => Error::Io(context, e),
+            vote42::error::Error::Json(context, e) => Error::Json(context, e),
+            vote42::error::Error::Validation(msg) => Error::Validation(msg),
+            vote42::error::Error::Crypto(msg) => Error::Crypto(msg),
+        }
+    }
+}
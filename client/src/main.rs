@@ -1,19 +1,32 @@
-use dirs;
 use std::fs;
 use std::fs::File;
 use std::io::{
     self, BufRead,
     BufReader,
-    stdin,
-    Error
+    stdin
 };
-use std::path::PathBuf;
-
-mod datatypes;
+use std::path::{Path, PathBuf};
+use std::process;
+
+use clap::Parser;
+use vote42::datatypes::Vote;
+use vote42::nonces::SiteNonce;
+use vote42::validation;
+
+mod cli;
+mod config;
+mod crypto;
+mod error;
+mod post;
 mod pre;
+mod ssh;
+mod template;
 mod utils;
 mod vote;
 
+use cli::{Cli, Command};
+use error::Error;
+
 const LOCAL_DIR: &str = ".vote42.rs/";        // name of local dir
 const SSH_LOCAL_DIR: &str = "ssh/";           // local dir for ssh stuff
 const CONFIG: &str = "config.json";           // name of config file in local directory
@@ -21,22 +34,24 @@ const HOST_DIR: &str = "hosts/";              // name of dir holding hosts confi
 const PRE_CONFIG: &str = "pre_server.json";   // name of pre-server config file in local directory
 const POST_CONFIG: &str = "post_server.json"; // name of post-server config file in local directory
 const RESULTS_DIR: &str = "results/";         // name of results dir
+const KEY_DIR: &str = "keys/";                // local dir for OpenPGP keys
 
 // make local directories
 // takes:
-//   $HOME (PathBuf)
-fn make_local_dirs(home_path: PathBuf) -> Result<(), Error> {
+//   local path (PathBuf)
+fn make_local_dirs(local_path: PathBuf) -> Result<(), Error> {
     // local dirs
     let local_dirs: Vec<String> = vec![
-        LOCAL_DIR.to_string(),
-        LOCAL_DIR.to_string() + SSH_LOCAL_DIR,
-        LOCAL_DIR.to_string() + HOST_DIR,
-        LOCAL_DIR.to_string() + RESULTS_DIR
+        String::new(),
+        SSH_LOCAL_DIR.to_string(),
+        HOST_DIR.to_string(),
+        RESULTS_DIR.to_string(),
+        KEY_DIR.to_string()
     ];
 
     // make all dirs in vec
     for dir in local_dirs {
-        utils::make_local_dir(home_path.clone(), dir.to_string())?;
+        utils::make_local_dir(local_path.clone(), dir)?;
     }
 
     Ok(())
@@ -60,17 +75,22 @@ fn make_local_dirs(home_path: PathBuf) -> Result<(), Error> {
 
 // make config file if it doesn't exist
 // takes:
-//   local path (PathBuf)
+//   path to default config file (PathBuf)
+//   path to config file in local directory (PathBuf)
 fn make_config(src_file_path: PathBuf, dest_file_path: PathBuf) -> Result<(), Error> {
     // check if config exists
-    if ! utils::check_file(src_file_path.clone()) {
-        let mut config_file_src = File::open(src_file_path.clone())?; // src config file
+    if ! utils::check_file(dest_file_path.clone()) {
+        // src config file
+        let mut config_file_src = File::open(src_file_path.clone())
+            .map_err(|e| Error::Io(format!("failed to open {:?}", src_file_path), e))?;
 
         // dest config file
-        let mut config_file_dest = File::create(dest_file_path.clone())?;
+        let mut config_file_dest = File::create(dest_file_path.clone())
+            .map_err(|e| Error::Io(format!("failed to create {:?}", dest_file_path), e))?;
 
         // copy src file to dest file
-        io::copy(&mut config_file_src, &mut config_file_dest)?;
+        io::copy(&mut config_file_src, &mut config_file_dest)
+            .map_err(|e| Error::Io(format!("failed to copy to {:?}", dest_file_path), e))?;
 
         println!(
             "file copied successfully: {:?} to {:?}",
@@ -87,220 +107,536 @@ fn make_config(src_file_path: PathBuf, dest_file_path: PathBuf) -> Result<(), Er
 // returns:
 //   path to file (PathBuf)
 //   isEncrypted (bool)
-fn check_ssh_private_key(local_path: PathBuf) -> Option<(PathBuf, bool)> {
+fn check_ssh_private_key(local_path: PathBuf) -> Result<(PathBuf, bool), Error> {
     let ssh_private_key_dir = local_path.join(SSH_LOCAL_DIR); // program's local ssh directory
 
     // check if there are files in ssh dir
-    match fs::read_dir(ssh_private_key_dir.clone()) {
-        Ok(entries) => {
-            // Count the number of files in the directory
-            let file_list: Vec<_> = entries
-                .filter_map(Result::ok) // Filter out any errors
-                .filter(|entry| entry.file_type().map(|ft| ft.is_file()).unwrap_or(false)) // Check if it's a file
-                .collect();
-
-            // Check if there is exactly one file
-            if file_list.len() == 1 {
-                // get file's name
-                let file_name = file_list[0].file_name();
-                let file_name: &str = match file_name.to_str() {
-                    Some(s) => s,
-                    None => {
-                        eprintln!("failed to get SSH key path from file name");
-                        return None;
-                    }
-                };
-
-                let file_path: PathBuf = ssh_private_key_dir.join(file_name); // get file's path
-                                                                              // let file = File::open(file_path.clone()).expect("failed to open file");
-                let file = match File::open(file_path.clone()) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        eprintln!("failed to open file: {}", e);
-                        return None;
-                    }
-                };
-                let mut reader = BufReader::new(file);
-                let mut header = String::new();
-                // let num_lines = reader.read_line(&mut header).expect("failed to read first line of file");
-                let num_lines = match reader.read_line(&mut header) {
-                    Ok(n) => n,
-                    Err(e) => {
-                        eprintln!("failed to read first line of file: {}", e);
-                        return None;
-                    }
-                };
-
-                // read forst line of file
-                if num_lines > 0 {
-                    // check if ssh key is encrypted or not
-                    if header.contains("ENCRYPTED") {
-                        return Some((file_path, true));
-                    } else if header.contains("PRIVATE KEY") {
-                        return Some((file_path, false));
-                    } else {
-                        eprintln!("E: file is not a ssh private key");
-                        return None;
-                    }
-                } else {
-                    eprintln!("E: first line is empty");
-                    return None;
-                }
-            } else {
-                eprintln!(
-                    "E: there should be exactly on key in {:?}. instead there are {}",
-                    ssh_private_key_dir,
-                    file_list.len()
-                );
-                eprintln!("E: make sure there is only one ssh key and that it is the right one");
-                return None;
-            }
-        }
-        Err(e) => {
-            eprintln!(
-                "E: failed to read the directory '{:?}': {}",
-                ssh_private_key_dir, e
-            );
-            return None;
-        }
+    let entries = fs::read_dir(ssh_private_key_dir.clone()).map_err(|e| {
+        Error::Io(format!("failed to read the directory {:?}", ssh_private_key_dir), e)
+    })?;
+
+    // Count the number of files in the directory
+    let file_list: Vec<_> = entries
+        .filter_map(Result::ok) // Filter out any errors
+        .filter(|entry| entry.file_type().map(|ft| ft.is_file()).unwrap_or(false)) // Check if it's a file
+        .collect();
+
+    // Check if there is exactly one file
+    if file_list.len() != 1 {
+        return Err(Error::KeyDetection(format!(
+            "there should be exactly one key in {:?}, instead there are {}. \
+             make sure there is only one ssh key and that it is the right one",
+            ssh_private_key_dir,
+            file_list.len()
+        )));
+    }
+
+    let file_path: PathBuf = file_list[0].path(); // get file's path
+    let file = File::open(file_path.clone())
+        .map_err(|e| Error::Io(format!("failed to open file {:?}", file_path), e))?;
+    let mut reader = BufReader::new(file);
+    let mut header = String::new();
+    let num_lines = reader
+        .read_line(&mut header)
+        .map_err(|e| Error::Io(format!("failed to read first line of {:?}", file_path), e))?;
+
+    // read first line of file
+    if num_lines == 0 {
+        return Err(Error::KeyDetection(format!("first line of {:?} is empty", file_path)));
+    }
+
+    // check if ssh key is encrypted or not
+    if header.contains("ENCRYPTED") {
+        Ok((file_path, true))
+    } else if header.contains("PRIVATE KEY") {
+        Ok((file_path, false))
+    } else {
+        Err(Error::KeyDetection(format!("{:?} is not a ssh private key", file_path)))
     }
 }
 
-// DRIVER
-fn main() {
-    // LOCAL
-    // get home directory (~/)
-    let home_path: PathBuf = match dirs::home_dir() {
-        Some(path) => path,
-        None => {
-            eprintln!("E: could not find the home directory");
-            return;
+// wait until the user has looked at a config file
+// takes:
+//   path to config file (&Path)
+fn confirm_config(config_path: &Path) -> Result<(), Error> {
+    println!("MAKE ANY NECESSARY CHANGES TO {:?}", config_path);
+    println!("enter something to continue");
+
+    let mut cont = String::new();
+    stdin()
+        .read_line(&mut cont)
+        .map_err(|e| Error::Io("failed to read line".to_string(), e))?;
+
+    Ok(())
+}
+
+// get the path results of the election site are written to
+// takes:
+//   local path (&Path)
+//   config (&Config)
+// returns:
+//   path to results file (PathBuf)
+fn get_results_path(local_path: &Path, config: &config::Config) -> PathBuf {
+    local_path.join(RESULTS_DIR).join(config.get_election_site() + ".json")
+}
+
+// get the path an earlier revision of the results is kept at once it is
+// corrected
+// takes:
+//   local path (&Path)
+//   config (&Config)
+//   revision (u32)
+// returns:
+//   path to results file of revision (PathBuf)
+fn get_revision_path(local_path: &Path, config: &config::Config, revision: u32) -> PathBuf {
+    local_path
+        .join(RESULTS_DIR)
+        .join(format!("{}.rev{}.json", config.get_election_site(), revision))
+}
+
+// get the path the signed and encrypted results are written to
+// takes:
+//   local path (&Path)
+//   config (&Config)
+// returns:
+//   path to sealed results file (PathBuf)
+fn get_sealed_path(local_path: &Path, config: &config::Config) -> PathBuf {
+    let mut sealed_path: PathBuf = get_results_path(local_path, config);
+    sealed_path.as_mut_os_string().push(".pgp");
+    sealed_path
+}
+
+// make sure a file an earlier step writes is there
+// takes:
+//   path to file (&Path)
+//   command that writes the file (&str)
+fn require_file(file_path: &Path, command: &str) -> Result<(), Error> {
+    if file_path.is_file() {
+        return Ok(());
+    }
+
+    Err(Error::Io(
+        format!("{:?} is missing, run `client {}` first", file_path, command),
+        io::Error::from(io::ErrorKind::NotFound),
+    ))
+}
+
+// get local path (~/.vote42.rs/ unless another one is given)
+// takes:
+//   local directory given on the command line (Option<PathBuf>)
+// returns:
+//   local path (PathBuf)
+fn get_local_path(config_dir: Option<PathBuf>) -> Result<PathBuf, Error> {
+    if let Some(path) = config_dir {
+        return Ok(path);
+    }
+
+    match dirs::home_dir() {
+        Some(path) => Ok(path.join(LOCAL_DIR)),
+        None => Err(Error::Io(
+            "could not find the home directory".to_string(),
+            io::Error::from(io::ErrorKind::NotFound),
+        )),
+    }
+}
+
+// INIT
+// make the local directories and copy the default configs into them
+// takes:
+//   local path (&Path)
+//   isNonInteractive (bool)
+fn init(local_path: &Path, non_interactive: bool) -> Result<(), Error> {
+    make_local_dirs(local_path.to_path_buf())?;
+    println!("local dirs made");
+
+    let configs: Vec<(&str, PathBuf)> = vec![
+        (CONFIG, local_path.join(CONFIG)),
+        (PRE_CONFIG, local_path.join(HOST_DIR).join(PRE_CONFIG)),
+        (POST_CONFIG, local_path.join(HOST_DIR).join(POST_CONFIG)),
+    ];
+
+    for (name, config_path) in configs {
+        make_config(PathBuf::from(name), config_path.clone())?;
+
+        if !non_interactive {
+            confirm_config(&config_path)?;
         }
-    };
-    println!("HOME: {:?}", home_path);
+    }
 
-    // get local path (~/vote42.rs/)
-    let local_path: PathBuf = home_path.join(LOCAL_DIR);
-    println!("LOCAL: {:?}", local_path);
+    Ok(())
+}
 
-    // get config path (~/.vote42.rs/config.json)
-    let config_path: PathBuf = local_path.join(CONFIG);
-    println!("CONFIG: {:?}", config_path);
+// VERIFY
+// files from the pre-server have to be signed by the pre-server key
+// (~/.vote42.rs/keys/pre-pubring.pgp). that key is never downloaded but has
+// to be put there by hand, so neither a compromised pre-server nor anyone
+// on the way can hand out a new key with a forged template or post-server key
+// takes:
+//   local path (&Path)
+//   path to signed file, its signature next to it (PathBuf)
+//   what the file is, for the messages (&str)
+fn verify_pre_signed(local_path: &Path, file_path: PathBuf, what: &str) -> Result<(), Error> {
+    let pre_server_config_path: PathBuf = local_path.join(HOST_DIR).join(PRE_CONFIG);
+    let pre_pubring_name: String = utils::get_from_json(pre_server_config_path, "pre-pubring")?;
+    let pre_pubring_path: PathBuf = local_path.join(KEY_DIR).join(pre_pubring_name);
+
+    crypto::verify_file(pre_pubring_path, file_path)?;
+    println!("{} signature verified", what);
+
+    Ok(())
+}
 
-    // get pre server config path (~/.vote42.rs/hosts/pre_server.json)
+// get the path of the post-server key results are encrypted to
+// takes:
+//   local path (&Path)
+// returns:
+//   path to post-server public key (PathBuf)
+fn get_post_pubring_path(local_path: &Path) -> Result<PathBuf, Error> {
     let pre_server_config_path: PathBuf = local_path.join(HOST_DIR).join(PRE_CONFIG);
-    println!("PRE_CONFIG: {:?}", pre_server_config_path);
+    let post_pubring_name: String = utils::get_from_json(pre_server_config_path, "post-pubring")?;
 
-    // make local directories
-    match make_local_dirs(home_path.clone()) {
-        Ok(_) => println!("local dirs made"),
-        Err(e) => {
-            eprintln!("E: failed to make local dirs: {}", e);
-            return;
-        }
-    };
+    Ok(local_path.join(KEY_DIR).join(post_pubring_name))
+}
 
-    // CONFIG
-    // make the config file
-    match make_config(PathBuf::from(CONFIG), config_path.clone()) {
-        Ok(_) => {
-            println!("MAKE ANY NECESSARY CHANGES TO {:?}", config_path);
-            println!("enter something to continue");
+// FETCH
+// download the vote template, its signature and the post-server key, or
+// import them from a local path if there is no connection to the pre-server
+// takes:
+//   local path (&Path)
+//   path to vote template to import instead (Option<PathBuf>)
+//   path to post-server key to import along with it (Option<PathBuf>)
+//   path to nonce of the site to import along with it (Option<PathBuf>)
+fn fetch(
+    local_path: &Path,
+    offline: Option<PathBuf>,
+    post_pubring: Option<PathBuf>,
+    nonce: Option<PathBuf>,
+) -> Result<(), Error> {
+    let vote_template_local_path: PathBuf = match offline {
+        Some(vote_template_src_path) => {
+            // don't take anything that isn't signed by the pre-server
+            verify_pre_signed(local_path, vote_template_src_path.clone(), "vote template")?;
+            if let Some(post_pubring_src_path) = &post_pubring {
+                verify_pre_signed(local_path, post_pubring_src_path.clone(), "post-server key")?;
+            }
 
-            let mut cont = String::new();
-            stdin().read_line(&mut cont).expect("E: failed to read line");
-        },
-        Err(e) => {
-            eprintln!("E: failed to make config: {}", e);
-            return;
+            let p: PathBuf =
+                pre::import_pre_files(local_path.to_path_buf(), vote_template_src_path, post_pubring, nonce)?;
+            println!("pre files have been imported: {:?}", p);
+            p
         }
-    };
-
-    // make the pre-server config file
-    match make_config(PathBuf::from(PRE_CONFIG), pre_server_config_path.clone()) {
-        Ok(_) => {
-            println!("MAKE ANY NECESSARY CHANGES TO {:?}", pre_server_config_path);
-            println!("enter something to continue");
+        None => {
+            // check for (single!) ssh key and get it's path and isEncrypted
+            let ssh_private_key_tuple: (PathBuf, bool) =
+                check_ssh_private_key(local_path.to_path_buf())?;
 
-            let mut cont = String::new();
-            stdin().read_line(&mut cont).expect("E: failed to read line");
-        },
-        Err(e) => {
-            eprintln!("E: failed to make pre-server config: {}", e);
-            return;
+            let p: PathBuf = pre::get_pre_files(local_path.to_path_buf(), ssh_private_key_tuple)?;
+            println!("pre files have been received: {:?}", p);
+            p
         }
     };
 
-    // get Config from JSON
-    let config: datatypes::Config = match datatypes::Config::create_from_json(config_path) {
-        Ok(c) => c,
-        Err(e) => {
-            eprintln!("E: failed to parse JSON to Config struct: {}", e);
-            return;
-        }
+    verify_pre_signed(local_path, vote_template_local_path.clone(), "vote template")?;
+    verify_pre_signed(local_path, get_post_pubring_path(local_path)?, "post-server key")?;
+
+    // a broken template is better found now than on election night
+    template::require_valid(&vote_template_local_path)
+}
+
+// REVISION
+// get the revision of the results about to be entered
+// a correction supersedes the results written before, which are kept
+// along with their sealed file under their revision; without a correction
+// results that were written but not submitted yet are entered again
+// takes:
+//   local path (&Path)
+//   config (&Config)
+//   reason for a correction (Option<String>)
+// returns:
+//   revision (u32)
+fn next_revision(
+    local_path: &Path,
+    config: &config::Config,
+    correction: Option<&String>,
+) -> Result<u32, Error> {
+    let vote_file_path: PathBuf = get_results_path(local_path, config);
+
+    let previous: Option<u32> = match vote_file_path.is_file() {
+        true => Some(Vote::create_from_json(&vote_file_path)?.get_revision()),
+        false => None,
     };
 
-    // SSH KEY
-    // check for (single!) ssh key and get it's path and isEncrypted
-    let ssh_private_key_tuple: (PathBuf, bool) = match check_ssh_private_key(local_path.clone()) {
-        Some(t) => t,
-        None => {
-            eprintln!("E: failed to get SSH private key tuple");
-            return;
+    match (previous, correction) {
+        (None, None) => Ok(1),
+        (None, Some(_)) => Err(Error::Validation(format!(
+            "there are no results in {:?} to correct",
+            vote_file_path
+        ))),
+        (Some(revision), None) => {
+            eprintln!(
+                "W: revision {} in {:?} is entered again; if it was submitted already, enter a correction with --correction",
+                revision, vote_file_path
+            );
+            Ok(revision)
         }
-    };
+        (Some(revision), Some(_)) => {
+            // keep what was submitted before
+            let revision_path: PathBuf = get_revision_path(local_path, config, revision);
+            fs::copy(&vote_file_path, &revision_path)
+                .map_err(|e| Error::Io(format!("failed to keep {:?}", vote_file_path), e))?;
+
+            let sealed_path: PathBuf = get_sealed_path(local_path, config);
+            if sealed_path.is_file() {
+                let mut revision_sealed_path: PathBuf = revision_path.clone();
+                revision_sealed_path.as_mut_os_string().push(".pgp");
+                fs::rename(&sealed_path, &revision_sealed_path)
+                    .map_err(|e| Error::Io(format!("failed to keep {:?}", sealed_path), e))?;
+            }
+            println!("revision {} kept: {:?}", revision, revision_path);
 
-    // PRE SERVER
-    // get files from pre-server
-    let vote_template_local_path: PathBuf = PathBuf::from("/home/lorax/.vote42.rs/vote_template.json"); // DEBUG
-    /* let vote_template_local_path: PathBuf = match pre::get_pre_files(local_path.clone(), ssh_private_key_tuple.clone()) {
-        Ok(s) => {
-            println!("pre files have been received");
-            s
-        },
-        Err(e) => {
-            eprintln!("E: failed to get pre files: {}", e);
-            return
+            Ok(revision + 1)
         }
-    }; */
+    }
+}
+
+// ENTER
+// verify the vote template, enter the counts and write the results
+// takes:
+//   local path (&Path)
+//   isDoubleEntry (bool)
+//   reason for a correction (Option<String>)
+fn enter(local_path: &Path, double_entry: bool, correction: Option<String>) -> Result<(), Error> {
+    let pre_server_config_path: PathBuf = local_path.join(HOST_DIR).join(PRE_CONFIG);
+    let config: config::Config = config::Config::create_from_json(local_path.join(CONFIG))?;
+
+    // the template is the one fetched from the pre-server or imported
+    let vote_template_name: String = utils::get_from_json(pre_server_config_path.clone(), "vote_template")?;
+    let vote_template_local_path: PathBuf = local_path.join(vote_template_name);
+    require_file(&vote_template_local_path, "fetch")?;
+
+    verify_pre_signed(local_path, vote_template_local_path.clone(), "vote template")?;
+    template::require_valid(&vote_template_local_path)?;
+
+    // the nonce binds the results to this site and this election
+    let nonce_name: String = utils::get_from_json(pre_server_config_path, "nonce")?;
+    let nonce_local_path: PathBuf = local_path.join(nonce_name);
+    require_file(&nonce_local_path, "fetch")?;
+    let nonce: SiteNonce = SiteNonce::create_from_json(&nonce_local_path)?;
 
     // VOTE
-    let mut vote: datatypes::Vote = match datatypes::Vote::create_from_json(vote_template_local_path) {
-        Ok(v) => v,
-        Err(e) => {
-            eprintln!("E: failed to parse JSON to Vote struct: {}", e);
-            return;
-        }
-    };
+    let mut vote: Vote = Vote::create_from_json(&vote_template_local_path)?;
+
+    if nonce.get_site() != config.get_election_site() {
+        return Err(Error::Validation(format!(
+            "nonce was issued to {}, this is {}",
+            nonce.get_site(),
+            config.get_election_site()
+        )));
+    }
+    if nonce.get_election_id() != vote.get_election_id() {
+        return Err(Error::Validation(format!(
+            "nonce was issued for election {}, the vote template is for {}",
+            nonce.get_election_id(),
+            vote.get_election_id()
+        )));
+    }
 
     // set meta data
     vote.set_election_site(config.get_election_site());
     vote.set_election_admin(config.get_election_admin());
+    vote.set_nonce(nonce.get_nonce());
 
-    match vote::set_votes(&mut vote) {
-        Ok(_) => println!("votes successfully set"),
-        Err(e) => {
-            eprintln!("E: trouble getting votes: {}", e);
-            return;
+    if let Some(reason) = &correction {
+        if reason.trim().is_empty() {
+            return Err(Error::Validation("a correction needs a reason".to_string()));
         }
-    };
+    }
+
+    // with double entry nothing is written before both counts agree
+    if double_entry {
+        vote::set_votes_double(&mut vote)?;
+    } else {
+        vote::set_votes(&mut vote)?;
+    }
+    println!("votes successfully set");
 
     // add time at end, before write to file
     vote.set_datetime();
     println!("{:?}", vote);
 
+    // PLAUSIBILITY
+    // implausible counts are never written, so they can't be sent
+    let report: validation::Report = validation::check_vote(&vote);
+    for warning in &report.warnings {
+        eprintln!("W: {}", warning);
+    }
+    if !report.errors.is_empty() {
+        for error in &report.errors {
+            eprintln!("E: {}", error);
+        }
+        return Err(Error::Validation(format!(
+            "{} implausible count(s), results not written",
+            report.errors.len()
+        )));
+    }
+
+    // the revision is only counted up once the counts are written
+    let revision: u32 = next_revision(local_path, &config, correction.as_ref())?;
+    vote.set_revision(revision, correction);
+
     // WRITE FILE
-    let vote_file_path: String = String::from(config.get_election_site());
-    let vote_file_path: PathBuf = local_path.join(RESULTS_DIR).join(vote_file_path);
+    let vote_file_path: PathBuf = vote.write_to_json(get_results_path(local_path, &config))?;
+    println!("write successfull: {:?} (revision {})", vote_file_path, revision);
+
+    Ok(())
+}
+
+// SIGN
+// sign results with the client secret key (~/.vote42.rs/keys/client-secring.pgp)
+// and encrypt them to the post-server key (~/.vote42.rs/keys/post-pubring.pgp)
+// takes:
+//   local path (&Path)
+fn sign(local_path: &Path) -> Result<(), Error> {
+    let pre_server_config_path: PathBuf = local_path.join(HOST_DIR).join(PRE_CONFIG);
+    let config: config::Config = config::Config::create_from_json(local_path.join(CONFIG))?;
+
+    let vote_file_path: PathBuf = get_results_path(local_path, &config);
+    require_file(&vote_file_path, "enter")?;
+
+    let client_secring_name: String = utils::get_from_json(pre_server_config_path, "client-secring")?;
+    let client_secring_path: PathBuf = local_path.join(KEY_DIR).join(client_secring_name);
+    let post_pubring_path: PathBuf = get_post_pubring_path(local_path)?;
+
+    // results are only ever encrypted to a key the pre-server signed
+    verify_pre_signed(local_path, post_pubring_path.clone(), "post-server key")?;
 
-    match vote.write_to_json(vote_file_path.clone()) {
-        Ok(_) => println!("write successfull"),
+    let vote_sealed_path: PathBuf = crypto::seal_file(
+        client_secring_path,
+        post_pubring_path,
+        vote_file_path
+    )?;
+    println!("results signed and encrypted: {:?}", vote_sealed_path);
+
+    Ok(())
+}
+
+// SUBMIT
+// send sealed results to post-server
+// takes:
+//   local path (&Path)
+fn submit(local_path: &Path) -> Result<(), Error> {
+    let config: config::Config = config::Config::create_from_json(local_path.join(CONFIG))?;
+
+    let vote_sealed_path: PathBuf = get_sealed_path(local_path, &config);
+    require_file(&vote_sealed_path, "sign")?;
+
+    // check for (single!) ssh key and get it's path and isEncrypted
+    let ssh_private_key_tuple: (PathBuf, bool) = check_ssh_private_key(local_path.to_path_buf())?;
+
+    post::put_post_files(
+        local_path.to_path_buf(),
+        ssh_private_key_tuple,
+        vec![vote_sealed_path]
+    )?;
+    println!("results have been sent");
+
+    Ok(())
+}
+
+// STATUS
+// show which files of which step are there
+// takes:
+//   local path (&Path)
+fn status(local_path: &Path) -> Result<(), Error> {
+    // print one line per file
+    let show = |name: &str, file_path: &Path| {
+        let mark: &str = if file_path.is_file() { "x" } else { " " };
+        println!("[{}] {:<16} {:?}", mark, name, file_path);
+    };
+
+    let config_path: PathBuf = local_path.join(CONFIG);
+    let pre_server_config_path: PathBuf = local_path.join(HOST_DIR).join(PRE_CONFIG);
+    let post_server_config_path: PathBuf = local_path.join(HOST_DIR).join(POST_CONFIG);
+
+    println!("init:");
+    show("config", &config_path);
+    show("pre-server", &pre_server_config_path);
+    show("post-server", &post_server_config_path);
+    match check_ssh_private_key(local_path.to_path_buf()) {
+        Ok((path, is_encrypted)) => println!("[x] {:<16} {:?} (encrypted: {})", "ssh key", path, is_encrypted),
+        Err(e) => println!("[ ] {:<16} {}", "ssh key", e),
+    };
+
+    // the rest needs the configs
+    let config: config::Config = match config::Config::create_from_json(config_path) {
+        Ok(c) => c,
         Err(e) => {
-            eprintln!("E: failed to write vote to JSON file: {}", e);
-            return
+            println!("can't tell more without a config: {}", e);
+            return Ok(());
         }
     };
+    let key_path = |key: &str| -> Result<PathBuf, Error> {
+        Ok(local_path.join(KEY_DIR).join(utils::get_from_json(pre_server_config_path.clone(), key)?))
+    };
+
+    let vote_template_name: String = utils::get_from_json(pre_server_config_path.clone(), "vote_template")?;
+    let mut vote_template_sig_path: PathBuf = local_path.join(&vote_template_name);
+    vote_template_sig_path.as_mut_os_string().push(".sig");
+
+    println!("fetch:");
+    show("vote template", &local_path.join(&vote_template_name));
+    show("signature", &vote_template_sig_path);
+    show("post-server key", &key_path("post-pubring")?);
+    show("pre-server key", &key_path("pre-pubring")?);
+    show("nonce", &local_path.join(utils::get_from_json(pre_server_config_path.clone(), "nonce")?));
+
+    println!("enter:");
+    show("results", &get_results_path(local_path, &config));
+    if let Ok(vote) = Vote::create_from_json(&get_results_path(local_path, &config)) {
+        println!("    revision {}", vote.get_revision());
+    }
 
-    // POST SERVER
-    // ...
+    println!("sign:");
+    show("client key", &key_path("client-secring")?);
+    show("sealed results", &get_sealed_path(local_path, &config));
+
+    Ok(())
+}
+
+// DRIVER
+fn main() {
+    let cli: Cli = Cli::parse();
+
+    // LOCAL
+    let local_path: PathBuf = match get_local_path(cli.config_dir) {
+        Ok(p) => p,
+        Err(e) => {
+            eprintln!("E: {}", e);
+            process::exit(e.exit_code());
+        }
+    };
+    println!("LOCAL: {:?}", local_path);
+
+    let result: Result<(), Error> = match cli.command {
+        Command::Init => init(&local_path, cli.non_interactive),
+        Command::Fetch { offline, post_pubring, nonce } => fetch(&local_path, offline, post_pubring, nonce),
+        Command::Enter { double_entry, correction } => enter(&local_path, double_entry, correction),
+        Command::Sign => sign(&local_path),
+        Command::Submit => submit(&local_path),
+        Command::Status => status(&local_path),
+        Command::CheckTemplate { path } => template::require_valid(&path),
+        Command::Run { double_entry } => init(&local_path, cli.non_interactive)
+            .and_then(|_| fetch(&local_path, None, None, None))
+            .and_then(|_| enter(&local_path, double_entry, None))
+            .and_then(|_| sign(&local_path))
+            .and_then(|_| submit(&local_path)),
+    };
+
+    match result {
+        Ok(_) => println!("done"),
+        Err(e) => {
+            eprintln!("E: {}", e);
+            process::exit(e.exit_code());
+        }
+    };
 }
@@ -0,0 +1,122 @@
+use ssh2::Session;
+use std::fs::File;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::error::Error;
+use crate::ssh;
+use crate::utils;
+
+// upload file via ssh to server
+// the file is written to "<name>.part" first and only renamed to its
+// real name once it is complete, so the server never reads half a file
+// takes:
+//   reference to ssh session (&Session)
+//   local path to read file from (PathBuf)
+//   remote path to write file to (String)
+fn put_file(
+    session: &Session,
+    local_file_path: PathBuf,
+    remote_file_path: String,
+) -> Result<(), Error> {
+    // create SFTP session
+    let sftp = session
+        .sftp()
+        .map_err(|e| Error::Sftp(format!("failed to create SFTP session: {}", e)))?;
+
+    // read local file
+    let mut local_file = File::open(local_file_path.clone())
+        .map_err(|e| Error::Io(format!("failed to open local file {:?}", local_file_path), e))?;
+    let mut buffer = Vec::new();
+    local_file
+        .read_to_end(&mut buffer)
+        .map_err(|e| Error::Io(format!("failed to read local file {:?}", local_file_path), e))?;
+
+    // create remote file and write local file to it
+    let remote_part_path: String = remote_file_path.clone() + ".part";
+    let mut remote_file = sftp
+        .create(Path::new(remote_part_path.as_str()))
+        .map_err(|e| Error::Sftp(format!("failed to create remote file {}: {}", remote_part_path, e)))?;
+    remote_file
+        .write_all(&buffer)
+        .map_err(|e| Error::Sftp(format!("failed to write to remote file {}: {}", remote_part_path, e)))?;
+    drop(remote_file);
+
+    // hand the complete file over to the server
+    // SFTP v3 servers can't rename over an existing file, so the name has
+    // to be new
+    sftp.rename(
+        Path::new(remote_part_path.as_str()),
+        Path::new(remote_file_path.as_str()),
+        None,
+    )
+    .map_err(|e| Error::Sftp(format!("failed to rename remote file {}: {}", remote_part_path, e)))?;
+
+    println!("file uploaded successfully");
+    Ok(())
+}
+
+// make the name a file is uploaded under
+// every upload gets a new name, so a correction never meets an earlier
+// upload still waiting in the inbox
+// takes:
+//   name of local file, e.g. "<site>.json.pgp" (&str)
+// returns:
+//   name with the time of upload, e.g. "<site>.<time>.json.pgp" (String)
+fn get_upload_name(file_name: &str) -> Result<String, Error> {
+    let time = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| Error::Validation(format!("system time is before 1970: {}", e)))?;
+    let time: String = format!("{}{:09}", time.as_secs(), time.subsec_nanos());
+
+    Ok(match file_name.split_once('.') {
+        Some((site, extension)) => format!("{}.{}.{}", site, time, extension),
+        None => format!("{}.{}", file_name, time),
+    })
+}
+
+// send files to post-server
+// takes:
+//   local path (PathBuf)
+//   the ssh key path (PathBuf), ssh key isEncrypted (bool)
+//   local paths to files to send (Vec<PathBuf>)
+pub fn put_post_files(
+    local_path: PathBuf,
+    ssh_private_key_tuple: (PathBuf, bool),
+    file_paths: Vec<PathBuf>,
+) -> Result<(), Error> {
+    let post_server_json_path: &str = "hosts/post_server.json"; // get path to post_server.json
+
+    // get username and host
+    let username: String = utils::get_from_json(local_path.join(post_server_json_path), "username")?;
+    println!("USERNAME: {}", username);
+    let host: String = utils::get_from_json(local_path.join(post_server_json_path), "host")?;
+    let host_key: String = utils::get_from_json(local_path.join(post_server_json_path), "host_key")?;
+    // the inbox as the upload account sees it, e.g. inside its chroot
+    let mut inbox_remote_path: String = utils::get_from_json(local_path.join(post_server_json_path), "inbox")?;
+    if !inbox_remote_path.ends_with('/') {
+        inbox_remote_path.push('/');
+    }
+    println!("INBOX: {}", inbox_remote_path);
+
+    // connect and authenticate with server
+    let session: Session = ssh::create_session(host, username, host_key, ssh_private_key_tuple)?;
+
+    // send files
+    for file_path in file_paths {
+        // get remote path to file
+        let file_name: String = match file_path.file_name() {
+            Some(n) => n.to_string_lossy().to_string(),
+            None => {
+                return Err(Error::Validation(format!("path has no file name: {:?}", file_path)));
+            }
+        };
+        let file_remote_path: String = inbox_remote_path.clone() + &get_upload_name(&file_name)?;
+        println!("file_remote_path: {}", file_remote_path);
+
+        put_file(&session, file_path, file_remote_path)?;
+    }
+
+    Ok(())
+}
@@ -1,41 +1,43 @@
-use rpassword::prompt_password;
 use ssh2::Session;
-use std::io::{Error, Read, Write};
-use std::net::TcpStream;
+use std::io::{Read, Write};
 use std::path::{Path, PathBuf};
 
+use crate::error::Error;
+use crate::ssh;
 use crate::utils;
 
 // download file via ssh from server
 // takes:
-//   ssh session (Session)
+//   reference to ssh session (&Session)
 //   remote path to read file from (String)
 //   local path to write file to (String)
 fn get_file(
-    session: Session,
+    session: &Session,
     remote_file_path: String,
     local_file_path: String,
 ) -> Result<(), Error> {
     // create SFTP session
-    let sftp = session.sftp().expect("E: failed to create SFTP session");
+    let sftp = session
+        .sftp()
+        .map_err(|e| Error::Sftp(format!("failed to create SFTP session: {}", e)))?;
 
     // open remote file
     let mut remote_file = sftp
         .open(Path::new(remote_file_path.clone().as_str()))
-        .expect("E: failed to open remote file");
+        .map_err(|e| Error::Sftp(format!("failed to open remote file {}: {}", remote_file_path, e)))?;
 
-    // create local file
-    let mut local_file = std::fs::File::create(local_file_path.clone().as_str())
-        .expect("E: failed to create local file");
-
-    // read remote file and write it to local file
+    // read remote file
     let mut buffer = Vec::new();
     remote_file
         .read_to_end(&mut buffer)
-        .expect("E: failed to read remote file");
+        .map_err(|e| Error::Sftp(format!("failed to read remote file {}: {}", remote_file_path, e)))?;
+
+    // create local file and write remote file to it
+    let mut local_file = std::fs::File::create(local_file_path.clone().as_str())
+        .map_err(|e| Error::Io(format!("failed to create local file {}", local_file_path), e))?;
     local_file
         .write_all(&buffer)
-        .expect("E: failed to write to local file");
+        .map_err(|e| Error::Io(format!("failed to write to local file {}", local_file_path), e))?;
 
     println!("file downloaded successfully");
     Ok(())
@@ -53,73 +55,133 @@ pub fn get_pre_files(
 ) -> Result<PathBuf, Error> {
     let pre_server_json_path: &str = "hosts/pre_server.json"; // get path to pre_server.json
 
-    // get username, host and ssh private key path
-    let username: String = utils::get_from_json(local_path.join(pre_server_json_path), "username");
+    // get username and host
+    let username: String = utils::get_from_json(local_path.join(pre_server_json_path), "username")?;
     println!("USERNAME: {}", username);
-    let host: String = utils::get_from_json(local_path.join(pre_server_json_path), "host");
-    let host_local_path: String = "/home/".to_string() + &username + "/.vote42.rs/";
-    println!("HOST_LOCAL_PATH: {}", host_local_path);
-    let ssh_private_key_path = ssh_private_key_tuple.0;
+    let host: String = utils::get_from_json(local_path.join(pre_server_json_path), "host")?;
+    let host_key: String = utils::get_from_json(local_path.join(pre_server_json_path), "host_key")?;
+
+    // the pre-server's sftp root is its file root
+    let host_root_path: String = "/".to_string();
 
     // get remote path to vote template
     let vote_template_name: String =
-        utils::get_from_json(local_path.join(pre_server_json_path), "vote_template");
+        utils::get_from_json(local_path.join(pre_server_json_path), "vote_template")?;
     println!("vote_template_name: {}", vote_template_name);
-    let vote_template_remote_path: String = host_local_path + "srv/" + &vote_template_name;
+    let vote_template_remote_path: String = host_root_path.clone() + &vote_template_name;
     println!("vote_template_remote_path: {}", vote_template_remote_path);
     let vote_template_local_path: String =
-        local_path.to_string_lossy().to_string() + &vote_template_name;
+        local_path.join(&vote_template_name).to_string_lossy().to_string();
     println!("vote_template_local_path: {}", vote_template_local_path);
 
-    // create TCP connection to server
-    let tcp = TcpStream::connect(host).expect("E: failed to connect to sever");
-
-    // create a new ssh session
-    let mut session = Session::new().expect("E: failed to create SSH session");
-    session.set_tcp_stream(tcp);
-    session.handshake().expect("E: failed to handshake");
-
-    // check if key is password encrypted
-    if ssh_private_key_tuple.1 {
-        // get password for ssh key
-        println!("to use the ssh key you have to enter the password");
-        let ssh_private_key_password: String = prompt_password("> ").unwrap();
-
-        // authenticate with server using key and password
-        session
-            .userauth_pubkey_file(
-                username.as_str(),
-                None,
-                Path::new(&ssh_private_key_path),
-                Some(ssh_private_key_password.clone().as_str()),
-            )
-            .expect("E: authentication with password failed");
-    } else {
-        // authenticate with server using key without password
-        session
-            .userauth_pubkey_file(
-                username.as_str(),
-                None,
-                Path::new(&ssh_private_key_path),
-                None,
-            )
-            .expect("E: authentication without password failed");
-    }
-
-    // check for successful authentication
-    if !session.authenticated() {
-        panic!("E: authentication failed");
-    }
+    // the template's signature lies next to it as "<template>.sig"
+    let vote_template_sig_remote_path: String = vote_template_remote_path.clone() + ".sig";
+    let vote_template_sig_local_path: String = vote_template_local_path.clone() + ".sig";
+
+    // get remote path to post-server public key
+    let post_pubring_name: String =
+        utils::get_from_json(local_path.join(pre_server_json_path), "post-pubring")?;
+    let post_pubring_remote_path: String = host_root_path.clone() + &post_pubring_name;
+    println!("post_pubring_remote_path: {}", post_pubring_remote_path);
+    let post_pubring_local_path: String =
+        local_path.join("keys/").join(&post_pubring_name).to_string_lossy().to_string();
+    println!("post_pubring_local_path: {}", post_pubring_local_path);
+
+    // the key is signed by the pre-server like the template
+    let post_pubring_sig_remote_path: String = post_pubring_remote_path.clone() + ".sig";
+    let post_pubring_sig_local_path: String = post_pubring_local_path.clone() + ".sig";
+
+    // the nonce of the site lies in its own directory, named by its user
+    let nonce_name: String = utils::get_from_json(local_path.join(pre_server_json_path), "nonce")?;
+    let nonce_remote_path: String =
+        host_root_path.clone() + "sites/" + &username + "/" + &nonce_name;
+    println!("nonce_remote_path: {}", nonce_remote_path);
+    let nonce_local_path: String = local_path.join(&nonce_name).to_string_lossy().to_string();
+
+    // connect and authenticate with server
+    let session: Session = ssh::create_session(host, username, host_key, ssh_private_key_tuple)?;
 
     // get files
     get_file(
-        session,
+        &session,
         vote_template_remote_path,
         vote_template_local_path.clone(),
     )?;
+    get_file(&session, vote_template_sig_remote_path, vote_template_sig_local_path)?;
+    get_file(&session, post_pubring_remote_path, post_pubring_local_path)?;
+    get_file(&session, post_pubring_sig_remote_path, post_pubring_sig_local_path)?;
+    get_file(&session, nonce_remote_path, nonce_local_path)?;
 
     // make PathBuf from String
     let vote_template_local_path: PathBuf = PathBuf::from(vote_template_local_path);
 
     Ok(vote_template_local_path)
 }
+
+// copy a file into the local directory
+// takes:
+//   path to file to copy (&Path)
+//   path to copy it to (&Path)
+fn import_file(src_file_path: &Path, dest_file_path: &Path) -> Result<(), Error> {
+    std::fs::copy(src_file_path, dest_file_path).map_err(|e| {
+        Error::Io(format!("failed to copy {:?} to {:?}", src_file_path, dest_file_path), e)
+    })?;
+
+    println!("file imported successfully: {:?}", dest_file_path);
+    Ok(())
+}
+
+// import the files of the pre-server from a local path, for election
+// sites that can't reach the pre-server
+// the files end up where get_pre_files would put them
+// takes:
+//   local path (PathBuf)
+//   path to vote template, with its signature next to it (PathBuf)
+//   path to post-server public key, if there is one (Option<PathBuf>)
+//   path to nonce of the site, if there is one (Option<PathBuf>)
+// returns:
+//   local path to vote template (PathBuf)
+pub fn import_pre_files(
+    local_path: PathBuf,
+    vote_template_src_path: PathBuf,
+    post_pubring_src_path: Option<PathBuf>,
+    nonce_src_path: Option<PathBuf>,
+) -> Result<PathBuf, Error> {
+    let pre_server_json_path: &str = "hosts/pre_server.json"; // get path to pre_server.json
+
+    // the template is stored under the name the client expects, whatever
+    // it was called where it came from
+    let vote_template_name: String =
+        utils::get_from_json(local_path.join(pre_server_json_path), "vote_template")?;
+    let vote_template_local_path: PathBuf = local_path.join(&vote_template_name);
+
+    let mut vote_template_sig_src_path: PathBuf = vote_template_src_path.clone();
+    vote_template_sig_src_path.as_mut_os_string().push(".sig");
+    let mut vote_template_sig_local_path: PathBuf = vote_template_local_path.clone();
+    vote_template_sig_local_path.as_mut_os_string().push(".sig");
+
+    import_file(&vote_template_src_path, &vote_template_local_path)?;
+    import_file(&vote_template_sig_src_path, &vote_template_sig_local_path)?;
+
+    // the post-server key comes with its signature "<key>.sig" as well
+    if let Some(post_pubring_src_path) = post_pubring_src_path {
+        let post_pubring_name: String =
+            utils::get_from_json(local_path.join(pre_server_json_path), "post-pubring")?;
+        let post_pubring_local_path: PathBuf = local_path.join("keys/").join(post_pubring_name);
+
+        let mut post_pubring_sig_src_path: PathBuf = post_pubring_src_path.clone();
+        post_pubring_sig_src_path.as_mut_os_string().push(".sig");
+        let mut post_pubring_sig_local_path: PathBuf = post_pubring_local_path.clone();
+        post_pubring_sig_local_path.as_mut_os_string().push(".sig");
+
+        import_file(&post_pubring_src_path, &post_pubring_local_path)?;
+        import_file(&post_pubring_sig_src_path, &post_pubring_sig_local_path)?;
+    }
+
+    if let Some(nonce_src_path) = nonce_src_path {
+        let nonce_name: String = utils::get_from_json(local_path.join(pre_server_json_path), "nonce")?;
+        import_file(&nonce_src_path, &local_path.join(nonce_name))?;
+    }
+
+    Ok(vote_template_local_path)
+}
@@ -0,0 +1,88 @@
+use base64::engine::general_purpose::STANDARD_NO_PAD;
+use base64::Engine;
+use rpassword::prompt_password;
+use ssh2::{HashType, Session};
+use std::net::TcpStream;
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+
+// connect to a server and authenticate with the local ssh key
+// the server has to present the host key pinned in the host's config,
+// or nothing is sent to it
+// takes:
+//   host with port (String)
+//   username on host (String)
+//   fingerprint of host key as "SHA256:<base64>" (String)
+//   the ssh key path (PathBuf), ssh key isEncrypted (bool)
+// returns:
+//   authenticated ssh session (Session)
+pub fn create_session(
+    host: String,
+    username: String,
+    host_key: String,
+    ssh_private_key_tuple: (PathBuf, bool),
+) -> Result<Session, Error> {
+    let ssh_private_key_path = ssh_private_key_tuple.0;
+
+    // create TCP connection to server
+    let tcp = TcpStream::connect(host.clone())
+        .map_err(|e| Error::Io(format!("failed to connect to server {}", host), e))?;
+
+    // create a new ssh session
+    let mut session = Session::new()
+        .map_err(|e| Error::SshAuth(format!("failed to create session: {}", e)))?;
+    session.set_tcp_stream(tcp);
+    session
+        .handshake()
+        .map_err(|e| Error::SshAuth(format!("failed to handshake with {}: {}", host, e)))?;
+
+    // the same format as `ssh-keygen -l` prints
+    let fingerprint: String = match session.host_key_hash(HashType::Sha256) {
+        Some(hash) => "SHA256:".to_string() + &STANDARD_NO_PAD.encode(hash),
+        None => return Err(Error::SshAuth(format!("{} sent no host key", host))),
+    };
+    if fingerprint != host_key.trim() {
+        return Err(Error::SshAuth(format!(
+            "host key of {} is {}, expected {}; not connecting",
+            host, fingerprint, host_key
+        )));
+    }
+
+    // check if key is password encrypted
+    if ssh_private_key_tuple.1 {
+        // get password for ssh key
+        println!("to use the ssh key you have to enter the password");
+        let ssh_private_key_password: String = prompt_password("> ")
+            .map_err(|e| Error::Io("failed to read password".to_string(), e))?;
+
+        // authenticate with server using key and password
+        session
+            .userauth_pubkey_file(
+                username.as_str(),
+                None,
+                Path::new(&ssh_private_key_path),
+                Some(ssh_private_key_password.clone().as_str()),
+            )
+            .map_err(|e| Error::SshAuth(format!("authentication with password failed: {}", e)))?;
+    } else {
+        // authenticate with server using key without password
+        session
+            .userauth_pubkey_file(
+                username.as_str(),
+                None,
+                Path::new(&ssh_private_key_path),
+                None,
+            )
+            .map_err(|e| {
+                Error::SshAuth(format!("authentication without password failed: {}", e))
+            })?;
+    }
+
+    // check for successful authentication
+    if !session.authenticated() {
+        return Err(Error::SshAuth(format!("authentication as {} failed", username)));
+    }
+
+    Ok(session)
+}
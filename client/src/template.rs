@@ -0,0 +1,25 @@
+use std::path::Path;
+use vote42::template::check_template;
+
+use crate::error::Error;
+
+// check a vote template and fail with all problems if there are any
+// takes:
+//   path to template (&Path)
+pub fn require_valid(json_file_path: &Path) -> Result<(), Error> {
+    let problems: Vec<String> = check_template(json_file_path)?;
+    if problems.is_empty() {
+        println!("vote template is valid");
+        return Ok(());
+    }
+
+    for problem in &problems {
+        eprintln!("E: {}", problem);
+    }
+
+    Err(Error::Validation(format!(
+        "vote template {:?} has {} problem(s)",
+        json_file_path,
+        problems.len()
+    )))
+}
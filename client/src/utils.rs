@@ -1,22 +1,27 @@
-use serde_json::{Result, Value};
+use serde::de::Error as _;
+use serde_json::Value;
 use std::fs;
 use std::fs::File;
 use std::io::BufReader;
 use std::path::PathBuf;
 
+use crate::error::Error;
+
 // make a local directory based on name
 // takes:
-//   $HOME (PathBuf)
+//   path to parent directory (PathBuf)
 //   directory name (String)
-pub fn make_local_dir(home_path: PathBuf, dir_name: String) -> Result<()> {
-    let dir_path = home_path.join(dir_name);
+pub fn make_local_dir(parent_path: PathBuf, dir_name: String) -> Result<(), Error> {
+    let dir_path = parent_path.join(dir_name);
 
     // check if directory exists
     if !dir_path.exists() {
         // create directory
-        match fs::create_dir(&dir_path) {
+        match fs::create_dir_all(&dir_path) {
             Ok(_) => println!("directory created: {:?}", dir_path),
-            Err(e) => eprintln!("failed to create directory: {}", e),
+            Err(e) => {
+                return Err(Error::Io(format!("failed to create directory {:?}", dir_path), e));
+            }
         }
     } else {
         println!("directory already exists: {:?}", dir_path);
@@ -40,7 +45,7 @@ pub fn check_file(file_path: PathBuf) -> bool {
                 println!("path exists, but it is not a file: {:?}", file_path);
             }
 
-            return true;
+            true
         }
         Err(e) => {
             if e.kind() == std::io::ErrorKind::NotFound {
@@ -49,7 +54,7 @@ pub fn check_file(file_path: PathBuf) -> bool {
                 eprintln!("error checking file: {}", e);
             }
 
-            return false;
+            false
         }
     }
 }
@@ -60,35 +65,34 @@ pub fn check_file(file_path: PathBuf) -> bool {
 //   key to look for in JSON file (&str)
 // returns:
 //   the value of the key in the JSON file (String)
-pub fn get_from_json(file_path: PathBuf, key: &str) -> String {
-    let file = File::open(file_path).expect("E: unable to open file");
+pub fn get_from_json(file_path: PathBuf, key: &str) -> Result<String, Error> {
+    let file = File::open(file_path.clone())
+        .map_err(|e| Error::Io(format!("unable to open {:?}", file_path), e))?;
     let reader = BufReader::new(file);
 
     // Deserialize the JSON into a serde_json::Value
-    let json_data: Value = serde_json::from_reader(reader).expect("E: failed to deserialize JSON");
+    let json_data: Value = serde_json::from_reader(reader)
+        .map_err(|e| Error::Json(format!("failed to deserialize {:?}", file_path), e))?;
 
-    let data_string: String = json_data
+    let data_string: Option<String> = json_data
         .get(key)
         .and_then(|v| match v {
             Value::String(s) => Some(s.clone()),
-            Value::Number(n) => {
-                if let Some(i) = n.as_i64() {
-                    Some(i.to_string())
-                } else if let Some(f) = n.as_f64() {
-                    Some(f.to_string())
-                } else {
-                    None
-                }
-            }
+            Value::Number(n) => match n.as_i64() {
+                Some(i) => Some(i.to_string()),
+                None => n.as_f64().map(|f| f.to_string()),
+            },
             Value::Bool(b) => Some(b.to_string()),
             Value::Array(arr) => Some(format!("{:?}", arr)),
             Value::Object(obj) => Some(format!("{:?}", obj)),
-            Value::Null => {
-                panic!("E: field {} not found", key);
-                // None
-            }
-        })
-        .unwrap();
+            Value::Null => None,
+        });
 
-    data_string
+    match data_string {
+        Some(s) => Ok(s),
+        None => Err(Error::Json(
+            format!("failed to read {:?}", file_path),
+            serde_json::Error::custom(format!("field {} not found", key)),
+        )),
+    }
 }
@@ -1,28 +1,278 @@
-use std::io::{stdin, Error};
+use std::io::{self, stdin};
 
-use crate::datatypes;
+use vote42::datatypes;
 
-fn set_vote(party: &mut datatypes::Party) -> Result<(), Error> {
-    println!("Enter {}'s votes", party.get_name());
+use crate::error::Error;
 
-    let mut num = String::new();
-    stdin().read_line(&mut num).expect("E: failed to read line");
+// read one line from the terminal
+// the end of the input is an error, so the prompts asking again until
+// the input is valid don't ask forever when nothing more can come
+// returns:
+//   line without surrounding whitespace (String)
+fn read_line() -> Result<String, Error> {
+    let mut line = String::new();
+    let num_bytes: usize = stdin()
+        .read_line(&mut line)
+        .map_err(|e| Error::Io("failed to read line".to_string(), e))?;
 
-    let num: u32 = num.trim().parse().expect("E: failed to parse string");
-    party.set_votes(num);
+    if num_bytes == 0 {
+        return Err(Error::Io(
+            "failed to read line".to_string(),
+            io::Error::from(io::ErrorKind::UnexpectedEof),
+        ));
+    }
 
-    Ok(())
+    Ok(line.trim().to_string())
 }
 
-pub fn set_votes(vote: &mut datatypes::Vote) -> Result<(), Error> {
-    for party in vote.get_mut_parties() {
-        match set_vote(party) {
-            Ok(_) => println!("{} vote set to {}", party.get_name(), party.get_votes()),
-            Err(e) => {
-                return Err(e);
+// read a number of votes from the terminal
+// returns:
+//   num of votes (u32)
+fn read_votes() -> Result<u32, Error> {
+    let num: String = read_line()?;
+
+    num.parse().map_err(|e| {
+        Error::Validation(format!("{:?} is not a number of votes: {}", num, e))
+    })
+}
+
+// read a count, asking again until it is a number
+// takes:
+//   name of operator entering it (&str)
+//   name of count (&str)
+// returns:
+//   count (u32)
+fn read_votes_until_valid(operator: &str, name: &str) -> Result<u32, Error> {
+    loop {
+        println!("{}: enter {}", operator, name);
+
+        match read_votes() {
+            Ok(num) => return Ok(num),
+            Err(Error::Validation(msg)) => eprintln!("E: {}, try again", msg),
+            Err(e) => return Err(e),
+        };
+    }
+}
+
+// one count on the results form
+enum Count {
+    RegisteredVoters,
+    Voters,
+    BallotsCast,
+    InvalidBallots,
+    BlankBallots,
+    Party(usize, String),                // index and name of party
+    Candidate(usize, usize, String, String), // index of party and candidate, their names
+}
+
+impl Count {
+    // get the name the count is asked for with
+    // takes:
+    //   reference to count (&Count)
+    // returns:
+    //   name of count (String)
+    fn get_name(&self) -> String {
+        match self {
+            Count::RegisteredVoters => "registered voters".to_string(),
+            Count::Voters => "voters who voted".to_string(),
+            Count::BallotsCast => "ballots cast".to_string(),
+            Count::InvalidBallots => "invalid ballots".to_string(),
+            Count::BlankBallots => "blank ballots".to_string(),
+            Count::Party(_, name) => name.clone() + "'s votes",
+            Count::Candidate(_, _, party, name) => format!("{}'s votes ({})", name, party),
+        }
+    }
+}
+
+// one count of one contest on the results form
+struct Entry {
+    contest: usize, // index of contest
+    contest_name: String,
+    count: Count,
+}
+
+impl Entry {
+    // get the name the count is asked for with
+    // takes:
+    //   reference to entry (&Entry)
+    // returns:
+    //   name of count with its contest (String)
+    fn get_name(&self) -> String {
+        format!("{}: {}", self.contest_name, self.count.get_name())
+    }
+}
+
+// get all counts that are entered, in the order they are asked for: for
+// every contest the electorate data of the results form, then the votes
+// of every party or, if the template asks for it, of every candidate
+// the registered voters are only asked for if the template doesn't
+// have them
+// takes:
+//   reference to vote struct (&Vote)
+// returns:
+//   counts (Vec<Entry>)
+fn get_counts(vote: &datatypes::Vote) -> Vec<Entry> {
+    let mut entries: Vec<Entry> = Vec::new();
+
+    for (c, contest) in vote.get_contests().iter().enumerate() {
+        let mut counts: Vec<Count> = Vec::new();
+
+        match contest.get_electorate() {
+            Some(e) if e.get_registered_voters() > 0 => println!(
+                "{}: registered voters: {}",
+                contest.get_name(),
+                e.get_registered_voters()
+            ),
+            _ => counts.push(Count::RegisteredVoters),
+        };
+        counts.push(Count::Voters);
+        counts.push(Count::BallotsCast);
+        counts.push(Count::InvalidBallots);
+        counts.push(Count::BlankBallots);
+
+        for (i, party) in contest.get_parties().iter().enumerate() {
+            if !contest.has_candidate_votes() {
+                counts.push(Count::Party(i, party.get_name()));
+                continue;
+            }
+
+            for (j, candidate) in party.get_candidates().iter().enumerate() {
+                counts.push(Count::Candidate(i, j, party.get_name(), candidate.get_name()));
+            }
+        }
+
+        for count in counts {
+            entries.push(Entry {
+                contest: c,
+                contest_name: contest.get_name(),
+                count,
+            });
+        }
+    }
+
+    entries
+}
+
+// put entered counts into the vote
+// takes:
+//   mutable reference to vote struct (&mut Vote)
+//   counts (&[Entry])
+//   entered numbers, in the order of the counts (&[u32])
+fn set_counts(vote: &mut datatypes::Vote, entries: &[Entry], nums: &[u32]) {
+    for (entry, num) in entries.iter().zip(nums) {
+        let contest: &mut datatypes::Contest = &mut vote.get_mut_contests()[entry.contest];
+
+        match &entry.count {
+            Count::RegisteredVoters => contest.get_mut_electorate().set_registered_voters(*num),
+            Count::Voters => contest.get_mut_electorate().set_voters(*num),
+            Count::BallotsCast => contest.get_mut_electorate().set_ballots_cast(*num),
+            Count::InvalidBallots => contest.get_mut_electorate().set_invalid_ballots(*num),
+            Count::BlankBallots => contest.get_mut_electorate().set_blank_ballots(*num),
+            Count::Party(i, _) => contest.get_mut_parties()[*i].set_votes(*num),
+            Count::Candidate(i, j, _, _) => {
+                contest.get_mut_parties()[*i].get_mut_candidates()[*j].set_votes(*num)
             }
         };
+        println!("{} set to {}", entry.get_name(), num);
+    }
+
+    // party votes are the sum of their candidates' votes
+    for contest in vote.get_mut_contests() {
+        if !contest.has_candidate_votes() {
+            continue;
+        }
+
+        let contest_name: String = contest.get_name();
+        for party in contest.get_mut_parties() {
+            party.set_votes_from_candidates();
+            println!("{}: {} vote set to {}", contest_name, party.get_name(), party.get_votes());
+        }
+    }
+}
+
+// enter every count of the results form once, by the election admin
+// a typo asks for the count again instead of dropping what was entered,
+// the end of the input stops
+// takes:
+//   mutable reference to vote struct (&mut Vote)
+pub fn set_votes(vote: &mut datatypes::Vote) -> Result<(), Error> {
+    let admin: String = vote.get_election_admin();
+    let counts: Vec<Entry> = get_counts(vote);
+
+    let mut nums: Vec<u32> = Vec::new();
+    for count in &counts {
+        nums.push(read_votes_until_valid(&admin, &count.get_name())?);
     }
 
+    set_counts(vote, &counts, &nums);
+
+    Ok(())
+}
+
+// ask for the name of an operator
+// takes:
+//   which operator is asked for (&str)
+//   names that are already taken (&[String])
+// returns:
+//   name of operator (String)
+fn read_operator(which: &str, taken: &[String]) -> Result<String, Error> {
+    loop {
+        println!("Enter the name of the {} operator", which);
+
+        let name: String = read_line()?;
+        if name.is_empty() {
+            eprintln!("E: the name must not be empty");
+        } else if taken.contains(&name) {
+            eprintln!("E: both counts must be entered by different operators");
+        } else {
+            return Ok(name);
+        }
+    }
+}
+
+// enter every count of the results form twice, by two different operators
+// the first operator enters all counts, then the second one enters them
+// all again. counts that differ are shown and both operators have to
+// enter them again until they match
+// takes:
+//   mutable reference to vote struct (&mut Vote)
+pub fn set_votes_double(vote: &mut datatypes::Vote) -> Result<(), Error> {
+    let first: String = read_operator("first", &[])?;
+    let second: String = read_operator("second", std::slice::from_ref(&first))?;
+
+    let counts: Vec<Entry> = get_counts(vote);
+
+    // first pass
+    let mut first_nums: Vec<u32> = Vec::new();
+    for count in &counts {
+        first_nums.push(read_votes_until_valid(&first, &count.get_name())?);
+    }
+
+    println!("\n{} is done, hand over to {}\n", first, second);
+
+    // second pass
+    let mut second_nums: Vec<u32> = Vec::new();
+    for count in &counts {
+        second_nums.push(read_votes_until_valid(&second, &count.get_name())?);
+    }
+
+    // compare and enter again until both numbers agree
+    for (i, count) in counts.iter().enumerate() {
+        let name: String = count.get_name();
+
+        while first_nums[i] != second_nums[i] {
+            eprintln!(
+                "E: MISMATCH for {}: {} entered {}, {} entered {}",
+                name, first, first_nums[i], second, second_nums[i]
+            );
+
+            first_nums[i] = read_votes_until_valid(&first, &name)?;
+            second_nums[i] = read_votes_until_valid(&second, &name)?;
+        }
+    }
+
+    set_counts(vote, &counts, &first_nums);
+    vote.set_entered_by(vec![first, second]);
+
     Ok(())
 }
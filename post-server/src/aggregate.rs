@@ -0,0 +1,329 @@
+use chrono::Utc;
+use serde::Serialize;
+use std::io::Error;
+use std::path::Path;
+use vote42::datatypes::{Contest, Vote};
+use vote42::registry::Registry;
+use vote42::seats::{self, Method, SeatConfig};
+
+use crate::store;
+
+pub const TOTALS_JSON: &str = "totals.json"; // name of totals in JSON in totals dir
+const TOTALS_CSV: &str = "totals.csv";       // name of totals in CSV in totals dir
+
+// votes of one party summed up over all reported election sites
+#[derive(Debug, Serialize)]
+pub struct PartyTotals {
+    id: String,
+    name: String,
+    votes: u64,
+    // percent of the valid votes, if there are any
+    share: Option<f64>,
+    // seats allocated on the votes so far, if seats are allocated
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seats: Option<u32>,
+}
+
+// how the seats of a contest were allocated
+#[derive(Debug, Serialize)]
+pub struct SeatTotals {
+    method: Method,
+    seats: u32,
+    threshold: f64,
+    // the last seat was a tie and has to be decided by lot
+    tie: bool,
+}
+
+// counts of one contest summed up over all reported election sites
+// the electorate counts only include sites that reported them
+#[derive(Debug, Serialize)]
+pub struct ContestTotals {
+    name: String,
+    registered_voters: u64,
+    voters: u64,
+    ballots_cast: u64,
+    invalid_ballots: u64,
+    blank_ballots: u64,
+    valid_votes: u64,
+    // percent of the registered voters who voted, if any are registered
+    turnout: Option<f64>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    seats: Option<SeatTotals>,
+    parties: Vec<PartyTotals>,
+}
+
+// an election site whose counted result is a correction
+#[derive(Debug, Serialize)]
+pub struct Correction {
+    site: String,
+    revision: u32,
+    reason: Option<String>,
+}
+
+// everything the press office gets on election night
+#[derive(Debug, Serialize)]
+pub struct Totals {
+    generated: String,
+    sites_registered: usize,
+    sites_reported: usize,
+    reported: Vec<String>,
+    outstanding: Vec<String>,
+    // reported sites whose counts were corrected
+    corrected: Vec<Correction>,
+    contests: Vec<ContestTotals>,
+}
+
+// divide as percent
+// takes:
+//   part (u64)
+//   whole (u64)
+// returns:
+//   part in percent of whole, none if whole is 0 (Option<f64>)
+fn percent(part: u64, whole: u64) -> Option<f64> {
+    match whole {
+        0 => None,
+        _ => Some(part as f64 / whole as f64 * 100.0),
+    }
+}
+
+// sum up one contest of the template over the results of all reported sites
+// takes:
+//   reference to contest of the template (&Contest)
+//   results of the reported sites (&[Vote])
+// returns:
+//   totals of contest (ContestTotals)
+fn sum_contest(template: &Contest, votes: &[Vote]) -> ContestTotals {
+    let contests: Vec<&Contest> = votes
+        .iter()
+        .filter_map(|v| v.get_contests().iter().find(|c| c.get_name() == template.get_name()))
+        .collect();
+
+    let mut totals = ContestTotals {
+        name: template.get_name(),
+        registered_voters: 0,
+        voters: 0,
+        ballots_cast: 0,
+        invalid_ballots: 0,
+        blank_ballots: 0,
+        valid_votes: 0,
+        turnout: None,
+        seats: None,
+        parties: Vec::new(),
+    };
+
+    for contest in &contests {
+        if let Some(electorate) = contest.get_electorate() {
+            totals.registered_voters += electorate.get_registered_voters() as u64;
+            totals.voters += electorate.get_voters() as u64;
+            totals.ballots_cast += electorate.get_ballots_cast() as u64;
+            totals.invalid_ballots += electorate.get_invalid_ballots() as u64;
+            totals.blank_ballots += electorate.get_blank_ballots() as u64;
+        }
+    }
+
+    // parties are matched by id and named as in the current template
+    for template_party in template.get_parties() {
+        let votes: u64 = contests
+            .iter()
+            .filter_map(|c| c.get_parties().iter().find(|p| p.get_id() == template_party.get_id()))
+            .map(|p| p.get_votes() as u64)
+            .sum();
+
+        totals.valid_votes += votes;
+        totals.parties.push(PartyTotals {
+            id: template_party.get_id(),
+            name: template_party.get_name(),
+            votes,
+            share: None,
+            seats: None,
+        });
+    }
+
+    for party in totals.parties.iter_mut() {
+        party.share = percent(party.votes, totals.valid_votes);
+    }
+    totals.turnout = percent(totals.voters, totals.registered_voters);
+
+    totals
+}
+
+// allocate the seats of a contest on its totals
+// takes:
+//   mutable reference to totals of contest (&mut ContestTotals)
+//   reference to seat config of contest (&SeatConfig)
+fn allocate_seats(totals: &mut ContestTotals, config: &SeatConfig) {
+    let votes: Vec<u64> = totals.parties.iter().map(|p| p.votes).collect();
+    let allocation: seats::Allocation = seats::allocate(
+        &votes,
+        config.get_seats(),
+        config.get_threshold_hundredths(),
+        config.get_method(),
+    );
+
+    for (party, seats) in totals.parties.iter_mut().zip(allocation.seats) {
+        party.seats = Some(seats);
+    }
+    if allocation.tie {
+        eprintln!("W: {}: the last seat is a tie and has to be decided by lot", totals.name);
+    }
+
+    totals.seats = Some(SeatTotals {
+        method: config.get_method(),
+        seats: config.get_seats(),
+        threshold: config.get_threshold(),
+        tie: allocation.tie,
+    });
+}
+
+// rebuild the totals from the current stored result of every registered
+// election site
+// stored results were checked when they were received, but are checked
+// again against the current template; a result that doesn't pass is
+// logged and its site counted as outstanding
+// takes:
+//   path to results directory (&Path)
+//   registry of election sites (&Registry)
+//   published vote template (&Vote)
+//   seats to allocate per contest (&[SeatConfig])
+// returns:
+//   totals (Totals)
+pub async fn rebuild(
+    results_path: &Path,
+    registry: &Registry,
+    template: &Vote,
+    seat_configs: &[SeatConfig],
+) -> Result<Totals, Error> {
+    let mut reported: Vec<String> = Vec::new();
+    let mut outstanding: Vec<String> = Vec::new();
+    let mut corrected: Vec<Correction> = Vec::new();
+    let mut votes: Vec<Vote> = Vec::new();
+
+    for site in registry.get_sites() {
+        let vote: Vote = match store::load_current(&results_path.join(site.get_site())).await? {
+            Some(v) => v,
+            None => {
+                outstanding.push(site.get_site());
+                continue;
+            }
+        };
+
+        if let Err(e) = vote.validate().and_then(|_| vote.matches_template(template)) {
+            eprintln!("E: current result of {} is not counted: {}", site.get_site(), e);
+            outstanding.push(site.get_site());
+            continue;
+        }
+
+        if vote.get_revision() > 1 {
+            corrected.push(Correction {
+                site: site.get_site(),
+                revision: vote.get_revision(),
+                reason: vote.get_reason(),
+            });
+        }
+        reported.push(site.get_site());
+        votes.push(vote);
+    }
+
+    let mut contests: Vec<ContestTotals> = template
+        .get_contests()
+        .iter()
+        .map(|c| sum_contest(c, &votes))
+        .collect();
+
+    for config in seat_configs {
+        match contests.iter_mut().find(|c| c.name == config.get_contest()) {
+            Some(totals) => allocate_seats(totals, config),
+            None => eprintln!("E: seats for unknown contest {}", config.get_contest()),
+        };
+    }
+
+    Ok(Totals {
+        generated: Utc::now().to_rfc3339(),
+        sites_registered: reported.len() + outstanding.len(),
+        sites_reported: reported.len(),
+        reported,
+        outstanding,
+        corrected,
+        contests,
+    })
+}
+
+// quote a CSV field if it needs to be
+// takes:
+//   field (&str)
+// returns:
+//   field as written to CSV (String)
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+impl Totals {
+    // write totals as CSV, one row per contest and party, each with the
+    // counts of its contest, so the file can be read without the JSON
+    // takes:
+    //   reference to totals (&Totals)
+    // returns:
+    //   CSV (String)
+    pub fn to_csv(&self) -> String {
+        let format_percent = |p: Option<f64>| match p {
+            Some(p) => format!("{:.2}", p),
+            None => String::new(),
+        };
+
+        let mut csv: String = String::from(
+            "contest,party_id,party,votes,share,seats,valid_votes,registered_voters,voters,\
+             ballots_cast,invalid_ballots,blank_ballots,turnout,sites_reported,sites_registered,\
+             sites_corrected\n",
+        );
+
+        for contest in &self.contests {
+            for party in &contest.parties {
+                csv += &format!(
+                    "{},{},{},{},{},{},{},{},{},{},{},{},{},{},{},{}\n",
+                    csv_field(&contest.name),
+                    csv_field(&party.id),
+                    csv_field(&party.name),
+                    party.votes,
+                    format_percent(party.share),
+                    party.seats.map(|s| s.to_string()).unwrap_or_default(),
+                    contest.valid_votes,
+                    contest.registered_voters,
+                    contest.voters,
+                    contest.ballots_cast,
+                    contest.invalid_ballots,
+                    contest.blank_ballots,
+                    format_percent(contest.turnout),
+                    self.sites_reported,
+                    self.sites_registered,
+                    self.corrected.len()
+                );
+            }
+        }
+
+        csv
+    }
+}
+
+// write totals as JSON and CSV to the totals directory
+// takes:
+//   path to totals directory (&Path)
+//   reference to totals (&Totals)
+pub async fn write_totals(totals_path: &Path, totals: &Totals) -> Result<(), Error> {
+    let json: String = serde_json::to_string_pretty(totals)?;
+
+    store::write_durable(&totals_path.join(TOTALS_JSON), json.as_bytes()).await?;
+    store::write_durable(&totals_path.join(TOTALS_CSV), totals.to_csv().as_bytes()).await?;
+
+    println!(
+        "totals written: {} of {} sites reported, {} corrected",
+        totals.sites_reported,
+        totals.sites_registered,
+        totals.corrected.len()
+    );
+
+    Ok(())
+}
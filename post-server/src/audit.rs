@@ -0,0 +1,413 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+
+const GENESIS: &str = "0000000000000000000000000000000000000000000000000000000000000000"; // previous hash of the first entry
+
+// what happened to a submission
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Event {
+    Accepted,  // first result of a site stored
+    Corrected, // further result of a site stored, superseding the earlier ones
+    Rejected,  // moved to the rejected directory
+}
+
+// everything an entry commits to
+// the hash of an entry is the SHA-256 of this serialized as JSON, so the
+// order of the fields must never change
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Record {
+    seq: u64,
+    time: String,
+    event: Event,
+    // name of the file as uploaded to the inbox
+    file: String,
+    // SHA-256 of the signed and encrypted file as received
+    payload_sha256: String,
+    // where the file was kept, relative to the local dir
+    stored: String,
+    // decrypted result stored next to it, relative to the local dir, and
+    // its SHA-256; the totals are summed up from it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    result_sha256: Option<String>,
+    // signing key and site, as far as they could be told
+    signer: Option<String>,
+    site: Option<String>,
+    // why a file was rejected or a result corrected
+    reason: Option<String>,
+    prev_hash: String,
+}
+
+// one line of the audit log
+#[derive(Debug, Serialize, Deserialize)]
+struct Entry {
+    #[serde(flatten)]
+    record: Record,
+    hash: String,
+}
+
+// what is known about a submission when it is logged
+pub struct Submission<'a> {
+    pub event: Event,
+    pub file: &'a str,
+    pub payload: &'a [u8],
+    pub stored: &'a Path,
+    // decrypted result stored and its contents, if one was stored
+    pub result: Option<(&'a Path, &'a [u8])>,
+    pub signer: Option<String>,
+    pub site: Option<String>,
+    pub reason: Option<String>,
+}
+
+// hash bytes with SHA-256
+// takes:
+//   data (&[u8])
+// returns:
+//   lower case hex (String)
+pub fn sha256_hex(data: &[u8]) -> String {
+    Sha256::digest(data)
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+// hash a record the way it is chained
+// takes:
+//   reference to record (&Record)
+// returns:
+//   hash of record (String)
+fn hash_record(record: &Record) -> Result<String, Error> {
+    Ok(sha256_hex(&serde_json::to_vec(record)?))
+}
+
+// a hash-chained, append-only log of every submission
+// every entry commits to the hash of the entry before it and to the hash
+// of the file as it was received, so no entry can be changed, removed or
+// put in between without breaking the chain
+pub struct AuditLog {
+    path: PathBuf,
+    seq: u64,
+    last_hash: String,
+    // SHA-256 of every file logged, to tell a file that was received before
+    seen: HashSet<String>,
+}
+
+// what a verified audit log holds
+pub struct Chain {
+    pub entries: u64,
+    pub last_hash: String,
+    // SHA-256 of every file logged
+    pub payloads: HashSet<String>,
+}
+
+impl AuditLog {
+    // open the audit log and check its chain
+    // a log that doesn't verify is never appended to
+    // takes:
+    //   path to log file (&Path)
+    // returns:
+    //   audit log ready to append (AuditLog)
+    pub async fn open(log_path: &Path) -> Result<Self, Error> {
+        let chain: Chain = verify(log_path, None).await?;
+
+        Ok(AuditLog {
+            path: log_path.to_path_buf(),
+            seq: chain.entries,
+            last_hash: chain.last_hash,
+            seen: chain.payloads,
+        })
+    }
+
+    // check if a file was logged before, whatever happened to it
+    // takes:
+    //   reference to audit log (&AuditLog)
+    //   file as received (&[u8])
+    // returns:
+    //   wasSeen (bool)
+    pub fn has_seen(&self, payload: &[u8]) -> bool {
+        self.seen.contains(&sha256_hex(payload))
+    }
+
+    // append an entry for a submission and sync it to disk
+    // takes:
+    //   mutable reference to audit log (&mut AuditLog)
+    //   submission to log (Submission)
+    //   local dir the stored path is relative to (&Path)
+    pub async fn append(&mut self, submission: Submission<'_>, local_path: &Path) -> Result<(), Error> {
+        let relative = |path: &Path| -> String {
+            path.strip_prefix(local_path)
+                .unwrap_or(path)
+                .to_string_lossy()
+                .to_string()
+        };
+
+        let payload_sha256: String = sha256_hex(submission.payload);
+        let record = Record {
+            seq: self.seq + 1,
+            time: Utc::now().to_rfc3339(),
+            event: submission.event,
+            file: submission.file.to_string(),
+            payload_sha256: payload_sha256.clone(),
+            stored: relative(submission.stored),
+            result: submission.result.map(|(path, _)| relative(path)),
+            result_sha256: submission.result.map(|(_, data)| sha256_hex(data)),
+            signer: submission.signer,
+            site: submission.site,
+            reason: submission.reason,
+            prev_hash: self.last_hash.clone(),
+        };
+        let hash: String = hash_record(&record)?;
+
+        let mut line: String = serde_json::to_string(&Entry { record, hash: hash.clone() })?;
+        line.push('\n');
+
+        let mut file = fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.path)
+            .await?;
+        file.write_all(line.as_bytes()).await?;
+        file.sync_all().await?;
+
+        self.seq += 1;
+        println!("audit log entry {}: {}", self.seq, hash);
+        self.last_hash = hash;
+        self.seen.insert(payload_sha256);
+
+        Ok(())
+    }
+}
+
+// recompute the chain of an audit log
+// takes:
+//   path to log file (&Path)
+//   local dir to check the stored files in, if they are to be checked (Option<&Path>)
+// returns:
+//   number of entries, hash of the last one and the files logged (Chain)
+pub async fn verify(log_path: &Path, local_path: Option<&Path>) -> Result<Chain, Error> {
+    let mut chain = Chain {
+        entries: 0,
+        last_hash: GENESIS.to_string(),
+        payloads: HashSet::new(),
+    };
+
+    let log: String = match fs::read_to_string(log_path).await {
+        Ok(l) => l,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(chain),
+        Err(e) => return Err(e),
+    };
+
+    for (i, line) in log.lines().enumerate() {
+        let broken = |msg: String| Error::other(format!("audit log line {}: {}", i + 1, msg));
+
+        let entry: Entry = serde_json::from_str(line).map_err(|e| broken(e.to_string()))?;
+
+        if entry.record.seq != chain.entries + 1 {
+            return Err(broken(format!(
+                "entry {} follows entry {}",
+                entry.record.seq, chain.entries
+            )));
+        }
+        if entry.record.prev_hash != chain.last_hash {
+            return Err(broken("previous hash doesn't match, an entry is missing or changed".to_string()));
+        }
+        if hash_record(&entry.record)? != entry.hash {
+            return Err(broken("hash doesn't match, the entry was changed".to_string()));
+        }
+
+        // the stored files must still be the ones that were received
+        if let Some(local_path) = local_path {
+            let mut files: Vec<(&String, &String)> =
+                vec![(&entry.record.stored, &entry.record.payload_sha256)];
+            if let (Some(result), Some(result_sha256)) =
+                (&entry.record.result, &entry.record.result_sha256)
+            {
+                files.push((result, result_sha256));
+            }
+
+            for (file, sha256) in files {
+                let stored_path = local_path.join(file);
+                match fs::read(&stored_path).await {
+                    Ok(data) if sha256_hex(&data) == *sha256 => {}
+                    Ok(_) => return Err(broken(format!("{:?} was changed", stored_path))),
+                    Err(e) => return Err(broken(format!("can't read {:?}: {}", stored_path, e))),
+                };
+            }
+        }
+
+        chain.entries = entry.record.seq;
+        chain.last_hash = entry.hash;
+        chain.payloads.insert(entry.record.payload_sha256);
+    }
+
+    Ok(chain)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // make a local dir with a log of three entries, the second one with a
+    // stored result
+    // takes:
+    //   name of test, to keep the dirs of the tests apart (&str)
+    // returns:
+    //   path to local dir (PathBuf)
+    async fn make_log(name: &str) -> PathBuf {
+        let local_path: PathBuf = std::env::temp_dir()
+            .join(format!("vote42-audit-{}-{}", std::process::id(), name));
+        let _ = fs::remove_dir_all(&local_path).await;
+        fs::create_dir_all(local_path.join("results")).await.unwrap();
+
+        let mut log: AuditLog = AuditLog::open(&local_path.join("audit.log")).await.unwrap();
+        for (i, file) in ["a.pgp", "b.pgp", "c.pgp"].iter().enumerate() {
+            let payload: Vec<u8> = format!("sealed {}", i).into_bytes();
+            let stored: PathBuf = local_path.join("results").join(file);
+            fs::write(&stored, &payload).await.unwrap();
+
+            let data: Vec<u8> = format!("result {}", i).into_bytes();
+            let result_path: PathBuf = stored.with_extension("json");
+            fs::write(&result_path, &data).await.unwrap();
+
+            let submission = Submission {
+                event: Event::Accepted,
+                file,
+                payload: &payload,
+                stored: &stored,
+                result: match i {
+                    1 => Some((&result_path, &data)),
+                    _ => None,
+                },
+                signer: None,
+                site: None,
+                reason: None,
+            };
+            log.append(submission, &local_path).await.unwrap();
+        }
+
+        local_path
+    }
+
+    // verify the log of a local dir and get the error
+    // takes:
+    //   path to local dir (&Path)
+    // returns:
+    //   error message (String)
+    async fn verify_err(local_path: &Path) -> String {
+        match verify(&local_path.join("audit.log"), Some(local_path)).await {
+            Ok(_) => panic!("audit log verified"),
+            Err(e) => e.to_string(),
+        }
+    }
+
+    // change the lines of the log of a local dir
+    // takes:
+    //   path to local dir (&Path)
+    //   change (FnOnce(&mut Vec<String>))
+    async fn edit_lines(local_path: &Path, edit: impl FnOnce(&mut Vec<String>)) {
+        let log_path: PathBuf = local_path.join("audit.log");
+        let mut lines: Vec<String> = fs::read_to_string(&log_path)
+            .await
+            .unwrap()
+            .lines()
+            .map(|l| l.to_string())
+            .collect();
+        edit(&mut lines);
+        fs::write(&log_path, lines.join("\n") + "\n").await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn untouched_log_verifies() {
+        let local_path: PathBuf = make_log("untouched").await;
+
+        let chain: Chain = verify(&local_path.join("audit.log"), Some(&local_path)).await.unwrap();
+        assert_eq!(chain.entries, 3);
+        assert_eq!(chain.payloads.len(), 3);
+
+        // and is appended to after the last entry
+        let log: AuditLog = AuditLog::open(&local_path.join("audit.log")).await.unwrap();
+        assert!(log.has_seen(b"sealed 2"));
+        assert!(!log.has_seen(b"sealed 3"));
+
+        fs::remove_dir_all(&local_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn edited_entry_fails() {
+        let local_path: PathBuf = make_log("edited").await;
+        edit_lines(&local_path, |lines| lines[1] = lines[1].replace("b.pgp", "x.pgp")).await;
+
+        let e: String = verify_err(&local_path).await;
+        assert!(e.starts_with("audit log line 2:"), "{}", e);
+
+        fs::remove_dir_all(&local_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn reordered_lines_fail() {
+        let local_path: PathBuf = make_log("reordered").await;
+        edit_lines(&local_path, |lines| lines.swap(1, 2)).await;
+
+        let e: String = verify_err(&local_path).await;
+        assert!(e.starts_with("audit log line 2:"), "{}", e);
+
+        fs::remove_dir_all(&local_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn deleted_line_fails() {
+        let local_path: PathBuf = make_log("deleted").await;
+        edit_lines(&local_path, |lines| {
+            lines.remove(1);
+        })
+        .await;
+
+        let e: String = verify_err(&local_path).await;
+        assert!(e.starts_with("audit log line 2:"), "{}", e);
+
+        fs::remove_dir_all(&local_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn changed_stored_file_fails() {
+        let local_path: PathBuf = make_log("changed").await;
+        fs::write(local_path.join("results/b.pgp"), b"sealed x").await.unwrap();
+
+        let e: String = verify_err(&local_path).await;
+        assert!(e.starts_with("audit log line 2:"), "{}", e);
+        assert!(e.contains("was changed"), "{}", e);
+
+        fs::remove_dir_all(&local_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn changed_result_fails() {
+        let local_path: PathBuf = make_log("result").await;
+        fs::write(local_path.join("results/b.json"), b"result x").await.unwrap();
+
+        let e: String = verify_err(&local_path).await;
+        assert!(e.starts_with("audit log line 2:"), "{}", e);
+
+        fs::remove_dir_all(&local_path).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn missing_stored_file_fails() {
+        let local_path: PathBuf = make_log("missing").await;
+        fs::remove_file(local_path.join("results/b.json")).await.unwrap();
+
+        let e: String = verify_err(&local_path).await;
+        assert!(e.starts_with("audit log line 2:"), "{}", e);
+        assert!(e.contains("can't read"), "{}", e);
+
+        fs::remove_dir_all(&local_path).await.unwrap();
+    }
+}
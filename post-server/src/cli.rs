@@ -0,0 +1,26 @@
+use clap::{Parser, Subcommand};
+use vote42::election::State;
+
+// command line of the post-server
+// without a command it serves, as it always has
+#[derive(Debug, Parser)]
+#[command(name = "post-server", version, about = "vote42.rs post-server")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// collect the results uploaded to the inbox (default)
+    Serve,
+    /// recompute the hash chain of the audit log and check the stored files against it
+    VerifyLog,
+    /// show the state of the election, or move it to the next state
+    /// (draft, published, accepting, closed, certified)
+    State {
+        /// state to move the election to
+        #[arg(value_name = "STATE")]
+        state: Option<State>,
+    },
+}
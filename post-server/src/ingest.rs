@@ -0,0 +1,443 @@
+use chrono::Utc;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+
+use sequoia_openpgp::cert::Cert;
+use sequoia_openpgp::crypto::KeyPair;
+use sequoia_openpgp::Fingerprint;
+use vote42::crypto;
+use vote42::datatypes::Vote;
+use vote42::election::{Election, State};
+use vote42::nonces::Nonces;
+use vote42::registry::{Registry, Site};
+use vote42::seats::{self, SeatConfig};
+use vote42::validation;
+
+use crate::aggregate;
+use crate::audit::{AuditLog, Event, Submission};
+use crate::store;
+use crate::LocalPaths;
+
+// parse and validate a single decrypted and verified file
+// takes:
+//   registry of election sites (&Registry)
+//   published vote template (&Vote)
+//   nonces issued to the election sites, or why there are none (&Result<Nonces, String>)
+//   decrypted result file (&[u8])
+//   fingerprint of signing certificate (&Fingerprint)
+// returns:
+//   vote struct (Vote) or the reason it was rejected (String)
+fn check_file(
+    registry: &Registry,
+    template: &Vote,
+    nonces: &Result<Nonces, String>,
+    data: &[u8],
+    signer: &Fingerprint,
+) -> Result<Vote, String> {
+    // a site may only submit results signed with its registered key
+    let site: &Site = registry
+        .get_by_fingerprint(signer)
+        .ok_or(format!("signing key {} is not registered for any site", signer))?;
+
+    let vote: Vote = Vote::from_slice(data)
+        .map_err(|e| format!("failed to parse JSON to Vote struct: {}", e))?;
+
+    vote.validate()?;
+
+    // parties are only known by their ids from here on
+    vote.matches_template(template)
+        .map_err(|e| format!("result doesn't match the vote template: {}", e))?;
+
+    // counts that can't be right are rejected, doubtful ones only logged
+    let report: validation::Report = validation::check_vote(&vote);
+    for warning in &report.warnings {
+        eprintln!("W: {}: {}", vote.get_election_site(), warning);
+    }
+    if !report.errors.is_empty() {
+        return Err(format!("implausible counts: {}", report.errors.join("; ")));
+    }
+
+    // and only for itself
+    if vote.get_election_site() != site.get_site() {
+        return Err(format!(
+            "result for {} signed by the key of {}",
+            vote.get_election_site(),
+            site.get_site()
+        ));
+    }
+
+    // with the nonce issued to it for this election
+    check_nonce(nonces, &site.get_site(), &vote)?;
+
+    Ok(vote)
+}
+
+// read the nonces the pre-server issued for the election of the template
+// takes:
+//   path to nonces file (&Path)
+//   id of the election of the vote template (&str)
+// returns:
+//   nonces (Nonces) or why there are none for the election (String)
+fn load_nonces(nonces_path: &Path, election_id: &str) -> Result<Nonces, String> {
+    match Nonces::create_from_json(nonces_path) {
+        Ok(n) if n.get_election_id() == election_id => Ok(n),
+        Ok(n) => Err(format!(
+            "{:?} is for election {}, the vote template for {}",
+            nonces_path,
+            n.get_election_id(),
+            election_id
+        )),
+        Err(e) => Err(e.to_string()),
+    }
+}
+
+// check that a result carries the nonce issued to its site
+// takes:
+//   nonces issued to the election sites, or why there are none (&Result<Nonces, String>)
+//   site id of the signing key (&str)
+//   reference to vote (&Vote)
+// returns:
+//   nothing or the reason the result is rejected (String)
+fn check_nonce(nonces: &Result<Nonces, String>, site: &str, vote: &Vote) -> Result<(), String> {
+    let nonces: &Nonces = nonces
+        .as_ref()
+        .map_err(|e| format!("the nonce can't be checked: {}", e))?;
+
+    match nonces.get(site) {
+        Some(nonce) if nonce == vote.get_nonce() => Ok(()),
+        Some(_) => Err(format!(
+            "nonce doesn't match the one issued to {} for election {}",
+            site,
+            nonces.get_election_id()
+        )),
+        None => Err(format!("no nonce was issued to {}", site)),
+    }
+}
+
+// move a file that could not be ingested to the rejected directory
+// and put the reason next to it
+// takes:
+//   path to rejected directory (&Path)
+//   path to file in inbox (&Path)
+//   reason for rejection (&str)
+// returns:
+//   path the file was moved to (PathBuf)
+async fn reject_file(rejected_path: &Path, file_path: &Path, reason: &str) -> Result<PathBuf, Error> {
+    let file_name = match file_path.file_name() {
+        Some(n) => n,
+        None => return Err(Error::other(format!("not a file: {:?}", file_path))),
+    };
+
+    // prefix with the time of rejection so repeated uploads don't collide
+    let rejected: String = Utc::now().format("%Y%m%dT%H%M%S%.6fZ-").to_string();
+    let dest_path: PathBuf = rejected_path.join(rejected + &file_name.to_string_lossy());
+    fs::rename(file_path, &dest_path).await?;
+    store::write_durable(&dest_path.with_extension("txt"), reason.as_bytes()).await?;
+
+    Ok(dest_path)
+}
+
+// reject a file and record it in the audit log
+// takes:
+//   paths in local dir (&LocalPaths)
+//   mutable reference to audit log (&mut AuditLog)
+//   path to file in inbox (&Path)
+//   sealed result file as received (&[u8])
+//   signing key, if the signature could be verified (Option<&Fingerprint>)
+//   registry of election sites (&Registry)
+//   reason for rejection (&str)
+async fn reject(
+    paths: &LocalPaths,
+    log: &mut AuditLog,
+    file_path: &Path,
+    sealed: &[u8],
+    signer: Option<&Fingerprint>,
+    registry: &Registry,
+    reason: &str,
+) -> Result<(), Error> {
+    eprintln!("E: rejected {:?}: {}", file_path, reason);
+    let rejected_path: PathBuf = reject_file(&paths.rejected, file_path, reason).await?;
+
+    log.append(
+        Submission {
+            event: Event::Rejected,
+            file: &file_path.file_name().unwrap_or_default().to_string_lossy(),
+            payload: sealed,
+            stored: &rejected_path,
+            result: None,
+            signer: signer.map(|s| s.to_hex()),
+            site: signer.and_then(|s| registry.get_by_fingerprint(s)).map(|s| s.get_site()),
+            reason: Some(reason.to_string()),
+        },
+        &paths.local,
+    )
+    .await
+}
+
+// what a run of the inbox loaded, so the next run only prints what changed
+#[derive(Default)]
+pub struct Loaded {
+    summary: String,
+    // certificates that couldn't be read
+    skipped: Vec<String>,
+}
+
+// process all complete result files waiting in the inbox
+// clients upload to a ".part" file and rename it once the upload is
+// done, so only ".pgp" files are picked up
+// the totals are rebuilt whenever a result was stored, and on the first
+// run, when there are none yet
+// what was loaded is only printed when it differs from the last run
+// takes:
+//   paths in local dir (&LocalPaths)
+//   mutable reference to what the last run loaded (&mut Loaded)
+pub async fn process_inbox(paths: &LocalPaths, loaded: &mut Loaded) -> Result<(), Error> {
+    // reload keys, registry, template, seats and nonces every run so new
+    // election sites and template revisions don't need a restart
+    let (certs, skipped): (Vec<Cert>, Vec<String>) = crypto::load_certs(&paths.keys)?;
+    let keys: Vec<KeyPair> = crypto::load_keypairs(&paths.secring)?;
+    let registry: Registry = Registry::create_from_json(&paths.registry)?;
+    let template: Vote = Vote::create_from_json(&paths.template)?;
+    let seat_configs: Vec<SeatConfig> = seats::create_from_json(&paths.seats)?;
+    // read every run, so a change of state needs no restart
+    let state: State = Election::load_or_create(&paths.election, &template.get_election_id())?.get_state();
+
+    // results are only taken with the nonces of the election of the
+    // template; missing or stale nonces reject the results that need
+    // them, so they are logged instead of stopping the inbox
+    let nonces: Result<Nonces, String> = load_nonces(&paths.nonces, &template.get_election_id());
+
+    let summary: String = format!(
+        "election {} is {}: {} sites registered, {} certificates, seats for {} contest(s), {}",
+        template.get_election_id(),
+        state,
+        registry.get_sites().len(),
+        certs.len(),
+        seat_configs.len(),
+        match &nonces {
+            Ok(n) => format!("nonces of {} sites", n.get_sites_count()),
+            Err(e) => format!("no nonces: {}", e),
+        }
+    );
+    if summary != loaded.summary {
+        println!("{}", summary);
+        loaded.summary = summary;
+    }
+    if skipped != loaded.skipped {
+        for s in &skipped {
+            eprintln!("E: {}", s);
+        }
+        loaded.skipped = skipped;
+    }
+
+    let mut log: AuditLog = AuditLog::open(&paths.audit_log).await?;
+
+    let mut stored: bool = false;
+    let mut entries = fs::read_dir(&paths.inbox).await?;
+
+    while let Some(entry) = entries.next_entry().await? {
+        let file_path: PathBuf = entry.path();
+
+        if !entry.file_type().await?.is_file()
+            || file_path.extension().and_then(|e| e.to_str()) != Some("pgp")
+        {
+            continue;
+        }
+
+        println!("new submission: {:?}", file_path);
+        let sealed: Vec<u8> = fs::read(&file_path).await?;
+
+        // a file received before is a replay, whatever happened to it then
+        if log.has_seen(&sealed) {
+            let reason: &str = "the same file was received before";
+            reject(paths, &mut log, &file_path, &sealed, None, &registry, reason).await?;
+            continue;
+        }
+
+        let (data, signer): (Vec<u8>, Fingerprint) =
+            match crypto::decrypt_verify(&keys, &certs, &sealed) {
+                Ok(t) => t,
+                Err(reason) => {
+                    reject(paths, &mut log, &file_path, &sealed, None, &registry, &reason).await?;
+                    continue;
+                }
+            };
+
+        // results sent before the election accepts them, e.g. tests, or
+        // after it was closed never end up in the totals
+        if !state.accepts_results() {
+            let reason: String = format!("the election is {}, results are not accepted", state);
+            reject(paths, &mut log, &file_path, &sealed, Some(&signer), &registry, &reason).await?;
+            continue;
+        }
+
+        let vote: Vote = match check_file(&registry, &template, &nonces, &data, &signer) {
+            Ok(v) => v,
+            Err(reason) => {
+                reject(paths, &mut log, &file_path, &sealed, Some(&signer), &registry, &reason).await?;
+                continue;
+            }
+        };
+
+        println!(
+            "result from {} revision {} signed by {}:",
+            vote.get_election_site(),
+            vote.get_revision(),
+            signer
+        );
+        for contest in vote.get_contests() {
+            let total: u64 = contest.get_parties().iter().map(|p| p.get_votes() as u64).sum();
+            println!(
+                "  {}: {} votes for {} parties",
+                contest.get_name(),
+                total,
+                contest.get_parties().len()
+            );
+        }
+
+        // a site that has sent a result before corrects it, and only a
+        // higher revision supersedes what is stored
+        let current: Option<Vote> =
+            store::load_current(&paths.results.join(vote.get_election_site())).await?;
+        let event: Event = match current {
+            Some(c) if vote.get_revision() <= c.get_revision() => {
+                let reason: String = format!(
+                    "revision {} doesn't supersede the stored revision {}",
+                    vote.get_revision(),
+                    c.get_revision()
+                );
+                reject(paths, &mut log, &file_path, &sealed, Some(&signer), &registry, &reason).await?;
+                continue;
+            }
+            Some(_) => Event::Corrected,
+            None => Event::Accepted,
+        };
+
+        // if storing fails the file stays in the inbox and is retried
+        let result_path: PathBuf =
+            store::store_result(&paths.results, &vote.get_election_site(), &data, &sealed).await?;
+        println!("result stored: {:?}", result_path);
+
+        let mut sealed_path: PathBuf = result_path.clone();
+        sealed_path.as_mut_os_string().push(".pgp");
+        log.append(
+            Submission {
+                event,
+                file: &file_path.file_name().unwrap_or_default().to_string_lossy(),
+                payload: &sealed,
+                stored: &sealed_path,
+                result: Some((&result_path, &data)),
+                signer: Some(signer.to_hex()),
+                site: Some(vote.get_election_site()),
+                reason: vote.get_reason(),
+            },
+            &paths.local,
+        )
+        .await?;
+
+        fs::remove_file(&file_path).await?;
+        stored = true;
+    }
+
+    if stored || fs::metadata(paths.totals.join(aggregate::TOTALS_JSON)).await.is_err() {
+        let totals: aggregate::Totals = aggregate::rebuild(&paths.results, &registry, &template, &seat_configs).await?;
+        aggregate::write_totals(&paths.totals, &totals).await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEMPLATE: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/../client/vote_template.json");
+
+    // make a result of a site carrying a nonce
+    // takes:
+    //   nonce (&str)
+    // returns:
+    //   result (Vote)
+    fn result(nonce: &str) -> Vote {
+        let mut vote: Vote = Vote::create_from_json(Path::new(TEMPLATE)).unwrap();
+        vote.set_election_site("site1".to_string());
+        vote.set_nonce(nonce.to_string());
+        vote
+    }
+
+    // write nonces to a file of its own for a test
+    // takes:
+    //   name of test (&str)
+    //   nonces (&Nonces)
+    // returns:
+    //   path to nonces file (PathBuf)
+    fn write_nonces(name: &str, nonces: &Nonces) -> PathBuf {
+        let nonces_path: PathBuf = std::env::temp_dir()
+            .join(format!("vote42-nonces-{}-{}.json", std::process::id(), name));
+        nonces.write_to_json(&nonces_path).unwrap();
+        nonces_path
+    }
+
+    // nonces of the election of the template, issued to site1 only
+    // returns:
+    //   nonces (Nonces)
+    fn issued() -> Nonces {
+        let template: Vote = Vote::create_from_json(Path::new(TEMPLATE)).unwrap();
+        let mut nonces: Nonces = Nonces::new(template.get_election_id());
+        nonces.insert("site1".to_string(), "n1".to_string());
+        nonces
+    }
+
+    #[test]
+    fn matching_nonce_is_accepted() {
+        assert_eq!(check_nonce(&Ok(issued()), "site1", &result("n1")), Ok(()));
+    }
+
+    #[test]
+    fn other_nonce_is_rejected() {
+        let e: String = check_nonce(&Ok(issued()), "site1", &result("n2")).unwrap_err();
+        assert!(e.starts_with("nonce doesn't match the one issued to site1"), "{}", e);
+    }
+
+    #[test]
+    fn site_without_nonce_is_rejected() {
+        let e: String = check_nonce(&Ok(issued()), "site2", &result("n1")).unwrap_err();
+        assert_eq!(e, "no nonce was issued to site2");
+    }
+
+    #[test]
+    fn nonces_of_stale_election_are_rejected() {
+        let template: Vote = Vote::create_from_json(Path::new(TEMPLATE)).unwrap();
+        let mut stale: Nonces = Nonces::new("stale-election".to_string());
+        stale.insert("site1".to_string(), "n1".to_string());
+        let nonces_path: PathBuf = write_nonces("stale", &stale);
+
+        // the same nonce, but issued for another election
+        let nonces: Result<Nonces, String> = load_nonces(&nonces_path, &template.get_election_id());
+        let e: String = check_nonce(&nonces, "site1", &result("n1")).unwrap_err();
+        assert!(e.starts_with("the nonce can't be checked"), "{}", e);
+        assert!(e.contains("is for election stale-election"), "{}", e);
+
+        std::fs::remove_file(&nonces_path).unwrap();
+    }
+
+    #[test]
+    fn nonces_of_election_are_loaded() {
+        let template: Vote = Vote::create_from_json(Path::new(TEMPLATE)).unwrap();
+        let nonces_path: PathBuf = write_nonces("current", &issued());
+
+        let nonces: Result<Nonces, String> = load_nonces(&nonces_path, &template.get_election_id());
+        assert_eq!(check_nonce(&nonces, "site1", &result("n1")), Ok(()));
+
+        std::fs::remove_file(&nonces_path).unwrap();
+    }
+
+    #[test]
+    fn missing_nonces_are_rejected() {
+        let nonces: Result<Nonces, String> =
+            load_nonces(Path::new("/nonexistent/nonces.json"), "bundestagswahl-2025");
+        let e: String = check_nonce(&nonces, "site1", &result("n1")).unwrap_err();
+        assert!(e.starts_with("the nonce can't be checked"), "{}", e);
+    }
+}
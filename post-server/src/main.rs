@@ -1,3 +1,147 @@
-fn main() {
-    println!("Hello, world!");
+use clap::Parser;
+use std::path::PathBuf;
+use std::process;
+use std::time::Duration;
+use tokio::fs;
+use vote42::election::{Election, State};
+
+mod aggregate;
+mod audit;
+mod cli;
+mod ingest;
+mod store;
+
+const LOCAL_DIR: &str = ".vote42.rs/";       // name of local dir
+const INBOX_DIR: &str = "inbox/";            // dir clients upload results to
+const RESULTS_DIR: &str = "results/";        // dir holding accepted results per election site
+const REJECTED_DIR: &str = "rejected/";      // dir holding results that failed validation
+const TOTALS_DIR: &str = "totals/";          // dir holding the totals over all election sites
+const KEY_DIR: &str = "keys/";               // dir holding the election sites' OpenPGP certificates
+const SECRING: &str = "post-secring.pgp";    // post-server OpenPGP secret key in local dir
+const REGISTRY: &str = "sites.json";         // registry of election sites in local dir
+const TEMPLATE: &str = "vote_template.json"; // vote template published by the pre-server in local dir
+const SEATS: &str = "seats.json";            // seats to allocate per contest in local dir
+const AUDIT_LOG: &str = "audit.log";         // hash-chained log of every submission in local dir
+const NONCES: &str = "nonces.json";          // nonces the pre-server issued to the election sites in local dir
+const ELECTION: &str = "election.json";      // election and its state in local dir
+const POLL_INTERVAL: u64 = 5;                // seconds between checks of the inbox
+
+// everything the post-server keeps in its local dir
+pub struct LocalPaths {
+    pub local: PathBuf,
+    pub inbox: PathBuf,
+    pub results: PathBuf,
+    pub rejected: PathBuf,
+    pub totals: PathBuf,
+    pub keys: PathBuf,
+    pub secring: PathBuf,
+    pub registry: PathBuf,
+    pub template: PathBuf,
+    pub seats: PathBuf,
+    pub audit_log: PathBuf,
+    pub nonces: PathBuf,
+    pub election: PathBuf,
+}
+
+// SERVE
+// process the inbox until the server is stopped
+// takes:
+//   paths in local dir (&LocalPaths)
+async fn serve(paths: &LocalPaths) -> std::io::Result<()> {
+    // make local directories
+    for dir in [&paths.inbox, &paths.results, &paths.rejected, &paths.totals, &paths.keys] {
+        fs::create_dir_all(dir).await?;
+    }
+
+    println!("waiting for results in {:?}", paths.inbox);
+
+    let mut interval = tokio::time::interval(Duration::from_secs(POLL_INTERVAL));
+    let mut loaded: ingest::Loaded = ingest::Loaded::default();
+    loop {
+        interval.tick().await;
+
+        // a failed run is logged and retried on the next tick
+        if let Err(e) = ingest::process_inbox(paths, &mut loaded).await {
+            eprintln!("E: failed to process inbox: {}", e);
+        }
+    }
+}
+
+// VERIFY LOG
+// recompute the audit log and exit with 1 if it doesn't verify
+// takes:
+//   paths in local dir (&LocalPaths)
+async fn verify_log(paths: &LocalPaths) {
+    match audit::verify(&paths.audit_log, Some(&paths.local)).await {
+        Ok(chain) => {
+            println!(
+                "audit log verified: {} entries, last hash {}",
+                chain.entries, chain.last_hash
+            );
+        }
+        Err(e) => {
+            eprintln!("E: audit log does not verify: {}", e);
+            process::exit(1);
+        }
+    };
+}
+
+// STATE
+// show the state of the election of the vote template, or move it to the
+// next state; exits with 1 if that isn't possible
+// takes:
+//   paths in local dir (&LocalPaths)
+//   state to move to (Option<State>)
+fn change_state(paths: &LocalPaths, state: Option<State>) {
+    match Election::change_state(&paths.election, &paths.template, state) {
+        Ok(election) => println!("election {} is {}", election.get_election_id(), election.get_state()),
+        Err(e) => {
+            eprintln!("E: {}", e);
+            process::exit(1);
+        }
+    };
+}
+
+// DRIVER
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    let cli: cli::Cli = cli::Cli::parse();
+
+    // get local path (~/.vote42.rs/)
+    let local_path: PathBuf = match dirs::home_dir() {
+        Some(path) => path.join(LOCAL_DIR),
+        None => {
+            eprintln!("E: could not find the home directory");
+            return Ok(());
+        }
+    };
+    println!("LOCAL: {:?}", local_path);
+
+    let paths = LocalPaths {
+        local: local_path.clone(),
+        inbox: local_path.join(INBOX_DIR),
+        results: local_path.join(RESULTS_DIR),
+        rejected: local_path.join(REJECTED_DIR),
+        totals: local_path.join(TOTALS_DIR),
+        keys: local_path.join(KEY_DIR),
+        secring: local_path.join(SECRING),
+        registry: local_path.join(REGISTRY),
+        template: local_path.join(TEMPLATE),
+        seats: local_path.join(SEATS),
+        audit_log: local_path.join(AUDIT_LOG),
+        nonces: local_path.join(NONCES),
+        election: local_path.join(ELECTION),
+    };
+
+    match cli.command {
+        None | Some(cli::Command::Serve) => serve(&paths).await,
+        Some(cli::Command::VerifyLog) => {
+            verify_log(&paths).await;
+            Ok(())
+        }
+        Some(cli::Command::State { state }) => {
+            change_state(&paths, state);
+            Ok(())
+        }
+    }
 }
@@ -0,0 +1,105 @@
+use chrono::Utc;
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::AsyncWriteExt;
+use vote42::datatypes::Vote;
+
+// write a file so that it is either completely on disk or not at all
+// the data goes to a temporary file first, which is synced and then
+// renamed over the destination
+// takes:
+//   path to destination file (&Path)
+//   file contents (&[u8])
+pub async fn write_durable(file_path: &Path, data: &[u8]) -> Result<(), Error> {
+    let tmp_path: PathBuf = file_path.with_extension("tmp");
+
+    let mut file = fs::File::create(&tmp_path).await?;
+    file.write_all(data).await?;
+    file.sync_all().await?;
+    drop(file);
+
+    fs::rename(&tmp_path, file_path).await?;
+
+    // sync the directory so the rename itself survives a crash
+    if let Some(dir) = file_path.parent() {
+        fs::File::open(dir).await?.sync_all().await?;
+    }
+
+    Ok(())
+}
+
+// store a received result in the directory of its election site
+// the sealed file as it was received is kept next to the decrypted
+// result, since only it carries the election site's signature
+// every submission is kept under the time it was received, so nothing
+// that has been accepted is ever overwritten, and every revision of a
+// corrected result stays there
+// takes:
+//   path to results directory (&Path)
+//   name of election site (&str)
+//   decrypted result file (&[u8])
+//   sealed result file as received (&[u8])
+// returns:
+//   path the result was stored at (PathBuf)
+pub async fn store_result(
+    results_path: &Path,
+    election_site: &str,
+    data: &[u8],
+    sealed: &[u8],
+) -> Result<PathBuf, Error> {
+    let site_path: PathBuf = results_path.join(election_site);
+    fs::create_dir_all(&site_path).await?;
+
+    let received: String = Utc::now().format("%Y%m%dT%H%M%S%.6fZ").to_string();
+    let result_path: PathBuf = site_path.join(received.clone() + ".json");
+    let sealed_path: PathBuf = site_path.join(received + ".json.pgp");
+
+    // sealed file first, so a stored result always has its signature
+    write_durable(&sealed_path, sealed).await?;
+    write_durable(&result_path, data).await?;
+
+    Ok(result_path)
+}
+
+// read the current result of an election site: the one with the highest
+// revision, and of those the one received last
+// only results that were validly signed are ever stored, so all of them
+// are candidates
+// takes:
+//   path to results directory of election site (&Path)
+// returns:
+//   current result, none if the site hasn't reported (Option<Vote>)
+pub async fn load_current(site_path: &Path) -> Result<Option<Vote>, Error> {
+    let mut entries = match fs::read_dir(site_path).await {
+        Ok(e) => e,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut current: Option<(u32, PathBuf, Vote)> = None;
+    while let Some(entry) = entries.next_entry().await? {
+        let file_path: PathBuf = entry.path();
+
+        // the sealed files next to the results end in ".json.pgp"
+        if file_path.extension().and_then(|e| e.to_str()) != Some("json") {
+            continue;
+        }
+
+        let data: Vec<u8> = fs::read(&file_path).await?;
+        let vote: Vote = Vote::from_slice(&data)
+            .map_err(|e| Error::other(format!("failed to parse {:?}: {}", file_path, e)))?;
+
+        // results are stored under the time they were received, so a later
+        // file name is a later result
+        let newer: bool = match &current {
+            None => true,
+            Some((revision, path, _)) => (vote.get_revision(), &file_path) > (*revision, path),
+        };
+        if newer {
+            current = Some((vote.get_revision(), file_path, vote));
+        }
+    }
+
+    Ok(current.map(|(_, _, vote)| vote))
+}
@@ -0,0 +1,24 @@
+use clap::{Parser, Subcommand};
+use vote42::election::State;
+
+// command line of the pre-server
+// without a command it serves, as it always has
+#[derive(Debug, Parser)]
+#[command(name = "pre-server", version, about = "vote42.rs pre-server")]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// sign the vote template and serve the file root (default)
+    Serve,
+    /// show the state of the election, or move it to the next state
+    /// (draft, published, accepting, closed, certified)
+    State {
+        /// state to move the election to
+        #[arg(value_name = "STATE")]
+        state: Option<State>,
+    },
+}
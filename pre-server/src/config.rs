@@ -0,0 +1,47 @@
+use serde::Deserialize;
+use std::fs::File;
+use std::io::{BufReader, Error};
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Deserialize)]
+pub struct Config {
+    bind: String,
+    root: PathBuf,
+}
+
+impl Config {
+    // popoulate Config struct from JSON file
+    // takes:
+    //   path to JSON file (&Path)
+    // returns:
+    //   config struct (Config)
+    pub fn create_from_json(json_file_path: &Path) -> Result<Self, Error> {
+        println!("attempting to read JSON config file: {:?}", json_file_path);
+        let file = File::open(json_file_path)?;
+        let reader = BufReader::new(file);
+
+        let config: Config = serde_json::from_reader(reader)?;
+
+        Ok(config)
+    }
+
+    // get address and port to listen on
+    // takes:
+    //   reference to config (&Config)
+    // returns:
+    //   address with port (String)
+    pub fn get_bind(&self) -> String {
+        self.bind.clone()
+    }
+
+    // get directory holding the files handed out to clients
+    // a relative root is taken relative to the local directory
+    // takes:
+    //   reference to config (&Config)
+    //   local path (&Path)
+    // returns:
+    //   path to file root (PathBuf)
+    pub fn get_root(&self, local_path: &Path) -> PathBuf {
+        local_path.join(&self.root)
+    }
+}
@@ -1,40 +1,223 @@
-use tokio::net::TcpListener;
-use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use clap::Parser;
+use std::fs::File;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process;
+use vote42::crypto;
+use vote42::datatypes::Vote;
+use vote42::election::{Election, State};
+use vote42::registry::Registry;
+use vote42::template;
 
+mod cli;
+mod config;
+mod nonce;
+mod sftp;
+mod srv;
+mod ssh;
+
+const LOCAL_DIR: &str = ".vote42.rs/";         // name of local dir
+const CONFIG: &str = "config.json";            // name of config file in local directory
+const SECRING: &str = "pre-secring.pgp";       // pre-server OpenPGP secret key in local dir
+const HOST_KEY: &str = "ssh_host_ed25519_key"; // ssh host key in local dir
+const REGISTRY: &str = "sites.json";           // registry of election sites in local dir
+const NONCES: &str = "nonces.json";            // nonces issued to the election sites in local dir
+const ELECTION: &str = "election.json";        // election and its state in local dir
+
+// make config file if it doesn't exist
+// takes:
+//   path to default config (PathBuf)
+//   path to config in local dir (PathBuf)
+fn make_config(src_file_path: PathBuf, dest_file_path: PathBuf) -> Result<(), io::Error> {
+    if !dest_file_path.exists() {
+        let mut config_file_src = File::open(src_file_path.clone())?;
+        let mut config_file_dest = File::create(dest_file_path.clone())?;
+
+        // copy src file to dest file
+        io::copy(&mut config_file_src, &mut config_file_dest)?;
+
+        println!(
+            "file copied successfully: {:?} to {:?}",
+            src_file_path, dest_file_path
+        );
+    }
+
+    Ok(())
+}
+
+// STATE
+// show the state of the election of the vote template, or move it to the
+// next state; exits with 1 if that isn't possible
+// takes:
+//   path to election file (&Path)
+//   path to vote template (&Path)
+//   state to move to (Option<State>)
+fn change_state(election_path: &Path, vote_template_path: &Path, state: Option<State>) {
+    match Election::change_state(election_path, vote_template_path, state) {
+        Ok(election) => println!("election {} is {}", election.get_election_id(), election.get_state()),
+        Err(e) => {
+            eprintln!("E: {}", e);
+            process::exit(1);
+        }
+    };
+}
+
+// DRIVER
 #[tokio::main]
-async fn main() -> std::io::Result<()> {
-    println!("Hello, world!");
-
-    let addr = "127.0.0.1:4242"; // adress and port to listen on
-    let listener = TcpListener::bind(addr).await?;
-    println!("listening for SSH connection on {}", addr);
-
-    loop {
-        // accept incoming connections
-        let (mut socket, _) = listener.accept().await?;
-        println!("new connection established");
-
-        // handle connection in a seperate task
-        tokio::spawn(async move {
-            let mut buffer = [0; 1024];
-            match socket.read(&mut buffer).await {
-                Ok(0) => {
-                    println!("connection closed");
-                    return;
-                }
-                Ok(n) => {
-                    // logic to handle SSH activity
-                    println!("received {} bytes: {:?}", n, &buffer[..n]);
-                }
-                Err(e) => {
-                    eprintln!("E: failed to read from socket: {}", e);
-                    return;
-                }
+async fn main() -> io::Result<()> {
+    let cli: cli::Cli = cli::Cli::parse();
+
+    // get local path (~/.vote42.rs/)
+    let local_path: PathBuf = match dirs::home_dir() {
+        Some(path) => path.join(LOCAL_DIR),
+        None => {
+            eprintln!("E: could not find the home directory");
+            return Ok(());
+        }
+    };
+    println!("LOCAL: {:?}", local_path);
+    std::fs::create_dir_all(&local_path)?;
+
+    // CONFIG
+    let config_path: PathBuf = local_path.join(CONFIG);
+    if let Err(e) = make_config(PathBuf::from(CONFIG), config_path.clone()) {
+        eprintln!("E: failed to make config: {}", e);
+        return Ok(());
+    }
+
+    let config: config::Config = match config::Config::create_from_json(&config_path) {
+        Ok(c) => c,
+        Err(e) => {
+            eprintln!("E: failed to parse JSON to Config struct: {}", e);
+            return Ok(());
+        }
+    };
+
+    // symlinks below the root are refused, the root itself may be one
+    let root_path: PathBuf = config.get_root(&local_path);
+    let root_path: PathBuf = root_path.canonicalize().unwrap_or(root_path);
+    println!("ROOT: {:?}", root_path);
+
+    let election_path: PathBuf = local_path.join(ELECTION);
+    if let Some(cli::Command::State { state }) = cli.command {
+        change_state(&election_path, &root_path.join(srv::VOTE_TEMPLATE), state);
+        return Ok(());
+    }
+
+    // REGISTRY
+    // only registered election sites can log in
+    let registry: Registry =
+        match Registry::create_from_json(&local_path.join(REGISTRY)) {
+            Ok(r) => r,
+            Err(e) => {
+                eprintln!("E: failed to load registry of election sites: {}", e);
+                return Ok(());
             }
+        };
+    for site in registry.get_sites() {
+        println!(
+            "registered site: {} ({}, admin: {})",
+            site.get_site(),
+            site.get_name(),
+            site.get_admin()
+        );
+    }
+
+    // check that all files clients expect are there
+    if let Err(e) = srv::check_layout(&root_path, &registry).await {
+        eprintln!("E: file root is incomplete: {}", e);
+        return Ok(());
+    }
 
-            if let Err(e) = socket.write_all(b"hello from Rust SSH listener").await {
-                eprintln!("failed to write to socket: {}", e);
+    // only sign and hand out a template that passes the same checks the
+    // clients run on it
+    let vote_template_path: PathBuf = root_path.join(srv::VOTE_TEMPLATE);
+    match template::check_template(&vote_template_path) {
+        Ok(problems) if problems.is_empty() => println!("vote template is valid"),
+        Ok(problems) => {
+            for problem in &problems {
+                eprintln!("E: {}", problem);
             }
-        });
+            eprintln!(
+                "E: vote template has {} problem(s), not signing it",
+                problems.len()
+            );
+            return Ok(());
+        }
+        Err(e) => {
+            eprintln!("E: vote template can't be checked: {}", e);
+            return Ok(());
+        }
+    };
+
+    let vote_template: Vote = match Vote::create_from_json(&vote_template_path) {
+        Ok(v) => v,
+        Err(e) => {
+            eprintln!("E: vote template can't be read: {}", e);
+            return Ok(());
+        }
+    };
+
+    // ELECTION
+    // the template is only handed out while the election is published or
+    // accepting results; the state is read again on every download
+    match Election::load_or_create(&election_path, &vote_template.get_election_id()) {
+        Ok(election) => println!(
+            "election {} is {}",
+            election.get_election_id(),
+            election.get_state()
+        ),
+        Err(e) => {
+            eprintln!("E: failed to load election: {}", e);
+            return Ok(());
+        }
+    };
+
+    // NONCES
+    // every site gets a nonce for the election of the template, which the
+    // post-server checks its results against
+    let issued = nonce::issue(&local_path.join(NONCES), &vote_template.get_election_id(), &registry)
+        .and_then(|nonces| nonce::publish(&root_path, &nonces, &registry));
+    if let Err(e) = issued {
+        eprintln!("E: failed to issue nonces: {}", e);
+        return Ok(());
     }
+
+    // sign the vote template and the post-server key, so clients can tell
+    // they haven't been tampered with on the way
+    // the server can't ask for a password, so the key has to be unencrypted
+    for file_path in [vote_template_path.clone(), root_path.join(srv::POST_PUBRING)] {
+        let signed = crypto::get_signing_keypair(&local_path.join(SECRING), || {
+            Err(vote42::error::Error::Crypto("the signing key must not be encrypted".to_string()))
+        })
+        .and_then(|keypair| crypto::sign_file(keypair, &file_path));
+        match signed {
+            Ok(_) => println!("signed: {:?}", file_path),
+            Err(e) => {
+                eprintln!("E: failed to sign {:?}: {}", file_path, e);
+                return Ok(());
+            }
+        };
+    }
+
+    // SSH
+    let host_key = match ssh::get_host_key(&local_path.join(HOST_KEY)) {
+        Ok(k) => k,
+        Err(e) => {
+            eprintln!("E: failed to get host key: {}", e);
+            return Ok(());
+        }
+    };
+
+    // clients pin this in their pre_server.json as "host_key"
+    println!(
+        "host key: {}",
+        host_key.public_key().fingerprint(russh::keys::ssh_key::HashAlg::Sha256)
+    );
+
+    // SERVE
+    let addr: String = config.get_bind(); // adress and port to listen on
+    println!("serving {:?} over sftp on {}", root_path, addr);
+
+    ssh::run(addr, host_key, root_path, election_path, registry).await
 }
@@ -0,0 +1,88 @@
+use std::io::{Error, ErrorKind};
+use std::path::{Path, PathBuf};
+use vote42::nonces::{Nonces, SiteNonce};
+use vote42::registry::Registry;
+
+use crate::srv;
+
+// make a new random nonce
+// returns:
+//   32 random bytes as lower case hex (String)
+fn random_nonce() -> Result<String, Error> {
+    let mut bytes: [u8; 32] = [0; 32];
+    getrandom::getrandom(&mut bytes)
+        .map_err(|e| Error::other(format!("failed to get random bytes: {}", e)))?;
+
+    Ok(bytes.iter().map(|b| format!("{:02x}", b)).collect())
+}
+
+// issue a nonce for the election to every registered election site
+// nonces already issued for the same election are kept, so a site that
+// fetched its nonce before a restart can still submit; a new election
+// gets new nonces for all sites
+// takes:
+//   path to nonces file in local dir (&Path)
+//   id of the election of the vote template (&str)
+//   registry of election sites (&Registry)
+// returns:
+//   nonces of all sites (Nonces)
+pub fn issue(nonces_path: &Path, election_id: &str, registry: &Registry) -> Result<Nonces, Error> {
+    let mut nonces: Nonces = match Nonces::create_from_json(nonces_path) {
+        Ok(n) if n.get_election_id() == election_id => n,
+        Ok(n) => {
+            println!(
+                "nonces were issued for election {}, issuing new ones for {}",
+                n.get_election_id(),
+                election_id
+            );
+            Nonces::new(election_id.to_string())
+        }
+        Err(vote42::error::Error::Io(_, e)) if e.kind() == ErrorKind::NotFound => {
+            Nonces::new(election_id.to_string())
+        }
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut issued: usize = 0;
+    for site in registry.get_sites() {
+        if nonces.get(&site.get_site()).is_none() {
+            nonces.insert(site.get_site(), random_nonce()?);
+            println!("nonce issued to {}", site.get_site());
+            issued += 1;
+        }
+    }
+    nonces.write_to_json(nonces_path)?;
+
+    // the post-server can't reach the pre-server, so the nonces are carried
+    // over by hand; until then it rejects results carrying the new ones
+    if issued > 0 {
+        println!(
+            "{} new nonce(s): copy {:?} to ~/.vote42.rs/nonces.json on the post-server",
+            issued, nonces_path
+        );
+    }
+
+    Ok(nonces)
+}
+
+// put the nonce of every election site into its directory in the file
+// root, where only that site can download it
+// takes:
+//   path to file root (&Path)
+//   nonces of all sites (&Nonces)
+//   registry of election sites (&Registry)
+pub fn publish(root_path: &Path, nonces: &Nonces, registry: &Registry) -> Result<(), Error> {
+    for site in registry.get_sites() {
+        let site_nonce: SiteNonce = nonces
+            .for_site(&site.get_site())
+            .ok_or(Error::other(format!("no nonce issued to {}", site.get_site())))?;
+
+        let nonce_path: PathBuf = root_path
+            .join(srv::SITES_DIR)
+            .join(site.get_site())
+            .join(srv::NONCE);
+        site_nonce.write_to_json(&nonce_path)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,481 @@
+use russh_sftp::protocol::{
+    Attrs, Data, File, FileAttributes, Handle, Name, OpenFlags, Status, StatusCode, Version,
+};
+use std::collections::HashMap;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use vote42::election::{Election, State};
+
+use crate::srv;
+
+// a read-only SFTP session over the file root
+// every election site sees the common files and its own directory
+// below "sites/", but not the directories of other sites
+pub struct SftpSession {
+    root_path: PathBuf,
+    election_path: PathBuf,
+    user: String,
+    version: Option<u32>,
+    handles: HashMap<String, PathBuf>,
+    dirs_read: HashMap<String, bool>,
+    next_handle: u64,
+}
+
+impl SftpSession {
+    // create session for an authenticated user
+    // takes:
+    //   path to file root (PathBuf)
+    //   path to election file (PathBuf)
+    //   name of authenticated user (String)
+    // returns:
+    //   sftp session (SftpSession)
+    pub fn new(root_path: PathBuf, election_path: PathBuf, user: String) -> Self {
+        SftpSession {
+            root_path,
+            election_path,
+            user,
+            version: None,
+            handles: HashMap::new(),
+            dirs_read: HashMap::new(),
+            next_handle: 0,
+        }
+    }
+
+    // turn a path sent by the client into the components of a path
+    // below the file root. ".." never leaves the root
+    // takes:
+    //   requested path (&str)
+    // returns:
+    //   path components (Vec<String>)
+    fn normalize(requested: &str) -> Vec<String> {
+        let mut components: Vec<String> = Vec::new();
+
+        for part in requested.split('/') {
+            match part {
+                "" | "." => {}
+                ".." => {
+                    components.pop();
+                }
+                p => components.push(p.to_string()),
+            }
+        }
+
+        components
+    }
+
+    // get the local path of a requested path, if the user may see it
+    // only the components are checked, symlinks inside the root are not
+    // followed here; get_attrs refuses them, so nothing is opened through one
+    // takes:
+    //   reference to session (&SftpSession)
+    //   requested path (&str)
+    // returns:
+    //   local path (PathBuf) or the reason access is denied (StatusCode)
+    fn resolve(&self, requested: &str) -> Result<PathBuf, StatusCode> {
+        let components: Vec<String> = Self::normalize(requested);
+
+        // "sites/<site>/..." is only visible to that site
+        if components.len() >= 2
+            && components[0] == srv::SITES_DIR.trim_end_matches('/')
+            && components[1] != self.user
+        {
+            return Err(StatusCode::PermissionDenied);
+        }
+
+        Ok(components
+            .iter()
+            .fold(self.root_path.clone(), |path, c| path.join(c)))
+    }
+
+    // check if a file of the election may be downloaded now
+    // the vote template, its signature and the nonces are only handed out
+    // while the election is published or accepting results; everything
+    // else, e.g. the keys, always is
+    // takes:
+    //   reference to session (&SftpSession)
+    //   local path (&Path)
+    // returns:
+    //   nothing or the reason access is denied (StatusCode)
+    fn check_state(&self, local_path: &Path) -> Result<(), StatusCode> {
+        let template_path: PathBuf = self.root_path.join(srv::VOTE_TEMPLATE);
+        let mut template_sig_path: PathBuf = template_path.clone();
+        template_sig_path.as_mut_os_string().push(".sig");
+
+        let is_election_file: bool = *local_path == template_path
+            || *local_path == template_sig_path
+            || local_path.file_name().and_then(|n| n.to_str()) == Some(srv::NONCE);
+        if !is_election_file {
+            return Ok(());
+        }
+
+        // read every time, so a change of state needs no restart
+        let state: State = match Election::create_from_json(&self.election_path) {
+            Ok(e) => e.get_state(),
+            Err(e) => {
+                eprintln!("E: can't tell the state of the election: {}", e);
+                return Err(StatusCode::PermissionDenied);
+            }
+        };
+
+        if !state.allows_template() {
+            eprintln!(
+                "E: {} can't download {:?}, the election is {}",
+                self.user, local_path, state
+            );
+            return Err(StatusCode::PermissionDenied);
+        }
+
+        Ok(())
+    }
+
+    // make a new handle for an opened file or directory
+    // takes:
+    //   mutable reference to session (&mut SftpSession)
+    //   local path (PathBuf)
+    // returns:
+    //   handle (String)
+    fn add_handle(&mut self, path: PathBuf) -> String {
+        let handle: String = self.next_handle.to_string();
+        self.next_handle += 1;
+        self.handles.insert(handle.clone(), path);
+        handle
+    }
+
+    // get the local path behind a handle
+    // takes:
+    //   reference to session (&SftpSession)
+    //   handle (&str)
+    // returns:
+    //   local path (PathBuf)
+    fn get_handle(&self, handle: &str) -> Result<PathBuf, StatusCode> {
+        self.handles.get(handle).cloned().ok_or(StatusCode::Failure)
+    }
+}
+
+// make a status reply
+// takes:
+//   request id (u32)
+//   status code (StatusCode)
+// returns:
+//   status (Status)
+fn status(id: u32, status_code: StatusCode) -> Status {
+    Status {
+        id,
+        status_code,
+        error_message: status_code.to_string(),
+        language_tag: "en-US".to_string(),
+    }
+}
+
+// get the attributes of a local file
+// a symlink could point into the directory of another site or out of the
+// file root, so symlinks are refused instead of followed
+// takes:
+//   local path (&Path)
+// returns:
+//   file attributes (FileAttributes)
+async fn get_attrs(path: &Path) -> Result<FileAttributes, StatusCode> {
+    match fs::symlink_metadata(path).await {
+        Ok(metadata) if metadata.file_type().is_symlink() => {
+            eprintln!("E: refused to follow symlink {:?}", path);
+            Err(StatusCode::PermissionDenied)
+        }
+        Ok(metadata) => Ok(FileAttributes::from(&metadata)),
+        Err(_) => Err(StatusCode::NoSuchFile),
+    }
+}
+
+impl russh_sftp::server::Handler for SftpSession {
+    type Error = StatusCode;
+
+    fn unimplemented(&self) -> Self::Error {
+        StatusCode::OpUnsupported
+    }
+
+    async fn init(
+        &mut self,
+        version: u32,
+        _extensions: HashMap<String, String>,
+    ) -> Result<Version, Self::Error> {
+        if self.version.is_some() {
+            eprintln!("E: duplicate SSH_FXP_VERSION packet");
+            return Err(StatusCode::ConnectionLost);
+        }
+
+        self.version = Some(version);
+        Ok(Version::new())
+    }
+
+    async fn realpath(&mut self, id: u32, path: String) -> Result<Name, Self::Error> {
+        let real_path: String = "/".to_string() + &Self::normalize(&path).join("/");
+
+        Ok(Name {
+            id,
+            files: vec![File::dummy(real_path)],
+        })
+    }
+
+    async fn stat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        let local_path: PathBuf = self.resolve(&path)?;
+
+        Ok(Attrs {
+            id,
+            attrs: get_attrs(&local_path).await?,
+        })
+    }
+
+    async fn lstat(&mut self, id: u32, path: String) -> Result<Attrs, Self::Error> {
+        self.stat(id, path).await
+    }
+
+    async fn fstat(&mut self, id: u32, handle: String) -> Result<Attrs, Self::Error> {
+        let local_path: PathBuf = self.get_handle(&handle)?;
+
+        Ok(Attrs {
+            id,
+            attrs: get_attrs(&local_path).await?,
+        })
+    }
+
+    async fn open(
+        &mut self,
+        id: u32,
+        filename: String,
+        pflags: OpenFlags,
+        _attrs: FileAttributes,
+    ) -> Result<Handle, Self::Error> {
+        // files can only be read
+        if pflags.intersects(
+            OpenFlags::WRITE | OpenFlags::APPEND | OpenFlags::CREATE | OpenFlags::TRUNCATE,
+        ) {
+            return Err(StatusCode::PermissionDenied);
+        }
+
+        let local_path: PathBuf = self.resolve(&filename)?;
+        if !get_attrs(&local_path).await?.is_regular() {
+            return Err(StatusCode::NoSuchFile);
+        }
+        self.check_state(&local_path)?;
+        println!("{} opened {:?}", self.user, local_path);
+
+        Ok(Handle {
+            id,
+            handle: self.add_handle(local_path),
+        })
+    }
+
+    async fn read(
+        &mut self,
+        id: u32,
+        handle: String,
+        offset: u64,
+        len: u32,
+    ) -> Result<Data, Self::Error> {
+        let local_path: PathBuf = self.get_handle(&handle)?;
+
+        let mut file = fs::File::open(&local_path)
+            .await
+            .map_err(|_| StatusCode::NoSuchFile)?;
+        file.seek(SeekFrom::Start(offset))
+            .await
+            .map_err(|_| StatusCode::Failure)?;
+
+        let mut data: Vec<u8> = Vec::new();
+        file.take(len as u64)
+            .read_to_end(&mut data)
+            .await
+            .map_err(|_| StatusCode::Failure)?;
+
+        if data.is_empty() {
+            return Err(StatusCode::Eof);
+        }
+
+        Ok(Data { id, data })
+    }
+
+    async fn close(&mut self, id: u32, handle: String) -> Result<Status, Self::Error> {
+        self.handles.remove(&handle);
+        self.dirs_read.remove(&handle);
+
+        Ok(status(id, StatusCode::Ok))
+    }
+
+    async fn opendir(&mut self, id: u32, path: String) -> Result<Handle, Self::Error> {
+        let local_path: PathBuf = self.resolve(&path)?;
+        if !get_attrs(&local_path).await?.is_dir() {
+            return Err(StatusCode::NoSuchFile);
+        }
+
+        let handle: String = self.add_handle(local_path);
+        self.dirs_read.insert(handle.clone(), false);
+
+        Ok(Handle { id, handle })
+    }
+
+    async fn readdir(&mut self, id: u32, handle: String) -> Result<Name, Self::Error> {
+        let local_path: PathBuf = self.get_handle(&handle)?;
+
+        // the whole directory is sent at once, the next call gets EOF
+        if self.dirs_read.insert(handle, true) != Some(false) {
+            return Err(StatusCode::Eof);
+        }
+
+        // listing "sites/" only shows the user's own directory
+        let is_sites_dir: bool = local_path == self.root_path.join(srv::SITES_DIR.trim_end_matches('/'));
+
+        let mut files: Vec<File> = Vec::new();
+        let mut entries = fs::read_dir(&local_path)
+            .await
+            .map_err(|_| StatusCode::NoSuchFile)?;
+        while let Ok(Some(entry)) = entries.next_entry().await {
+            let name: String = entry.file_name().to_string_lossy().to_string();
+            if is_sites_dir && name != self.user {
+                continue;
+            }
+
+            if let Ok(metadata) = entry.metadata().await {
+                files.push(File::new(name, FileAttributes::from(&metadata)));
+            }
+        }
+
+        Ok(Name { id, files })
+    }
+
+    async fn write(
+        &mut self,
+        _id: u32,
+        _handle: String,
+        _offset: u64,
+        _data: Vec<u8>,
+    ) -> Result<Status, Self::Error> {
+        Err(StatusCode::PermissionDenied)
+    }
+
+    async fn remove(&mut self, _id: u32, _filename: String) -> Result<Status, Self::Error> {
+        Err(StatusCode::PermissionDenied)
+    }
+
+    async fn mkdir(
+        &mut self,
+        _id: u32,
+        _path: String,
+        _attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        Err(StatusCode::PermissionDenied)
+    }
+
+    async fn rmdir(&mut self, _id: u32, _path: String) -> Result<Status, Self::Error> {
+        Err(StatusCode::PermissionDenied)
+    }
+
+    async fn rename(
+        &mut self,
+        _id: u32,
+        _oldpath: String,
+        _newpath: String,
+    ) -> Result<Status, Self::Error> {
+        Err(StatusCode::PermissionDenied)
+    }
+
+    async fn setstat(
+        &mut self,
+        _id: u32,
+        _path: String,
+        _attrs: FileAttributes,
+    ) -> Result<Status, Self::Error> {
+        Err(StatusCode::PermissionDenied)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // make a session of site "me" over a file root that doesn't have to exist
+    // returns:
+    //   sftp session (SftpSession)
+    fn session() -> SftpSession {
+        SftpSession::new(
+            PathBuf::from("/srv/root"),
+            PathBuf::from("/srv/election.json"),
+            "me".to_string(),
+        )
+    }
+
+    #[test]
+    fn normalize_never_leaves_root() {
+        assert_eq!(SftpSession::normalize("../../etc/passwd"), vec!["etc", "passwd"]);
+        assert_eq!(SftpSession::normalize("/sites/me/../other"), vec!["sites", "other"]);
+        assert_eq!(SftpSession::normalize("sites/./other/"), vec!["sites", "other"]);
+        assert!(SftpSession::normalize("/..").is_empty());
+    }
+
+    #[test]
+    fn own_site_resolves() {
+        assert_eq!(
+            session().resolve("sites/me/nonce.json"),
+            Ok(PathBuf::from("/srv/root/sites/me/nonce.json"))
+        );
+        assert_eq!(
+            session().resolve("/sites/me/./nonce.json"),
+            Ok(PathBuf::from("/srv/root/sites/me/nonce.json"))
+        );
+        assert_eq!(session().resolve("sites"), Ok(PathBuf::from("/srv/root/sites")));
+    }
+
+    #[test]
+    fn other_site_is_denied() {
+        for requested in [
+            "sites/other/nonce.json",
+            "/sites/other/nonce.json",
+            "/sites/me/../other/nonce.json",
+            "sites/./other",
+            "sites//other",
+            "../sites/other/nonce.json",
+        ] {
+            assert_eq!(
+                session().resolve(requested),
+                Err(StatusCode::PermissionDenied),
+                "{}",
+                requested
+            );
+        }
+    }
+
+    #[test]
+    fn dot_dot_stays_inside_root() {
+        assert_eq!(
+            session().resolve("../../etc/passwd"),
+            Ok(PathBuf::from("/srv/root/etc/passwd"))
+        );
+        assert_eq!(
+            session().resolve("sites/me/../../../vote_template.json"),
+            Ok(PathBuf::from("/srv/root/vote_template.json"))
+        );
+    }
+
+    #[tokio::test]
+    async fn symlinks_are_refused() {
+        let root_path: PathBuf =
+            std::env::temp_dir().join(format!("vote42-sftp-{}", std::process::id()));
+        let _ = fs::remove_dir_all(&root_path).await;
+        fs::create_dir_all(root_path.join("sites/other")).await.unwrap();
+        fs::create_dir_all(root_path.join("sites/me")).await.unwrap();
+        fs::write(root_path.join("sites/other/nonce.json"), b"{}").await.unwrap();
+        std::os::unix::fs::symlink(
+            root_path.join("sites/other/nonce.json"),
+            root_path.join("sites/me/nonce.json"),
+        )
+        .unwrap();
+
+        assert!(get_attrs(&root_path.join("sites/other/nonce.json")).await.is_ok());
+        assert_eq!(
+            get_attrs(&root_path.join("sites/me/nonce.json")).await.err(),
+            Some(StatusCode::PermissionDenied)
+        );
+
+        fs::remove_dir_all(&root_path).await.unwrap();
+    }
+}
@@ -0,0 +1,43 @@
+use std::io::Error;
+use std::path::{Path, PathBuf};
+use tokio::fs;
+use vote42::registry::Registry;
+
+pub const VOTE_TEMPLATE: &str = "vote_template.json"; // name of vote template in file root
+pub const PRE_PUBRING: &str = "pre-pubring.pgp";      // pre-server OpenPGP public key in file root
+pub const POST_PUBRING: &str = "post-pubring.pgp";    // post-server OpenPGP public key in file root
+pub const SITES_DIR: &str = "sites/";                 // dir in file root holding a dir per election site
+pub const NONCE: &str = "nonce.json";                 // nonce of an election site in its dir
+pub const CLIENT_PUBRING: &str = "client-pubring.pgp"; // OpenPGP public key of an election site in its dir
+
+// check that the file root holds everything clients download
+// takes:
+//   path to file root (&Path)
+//   registry of election sites (&Registry)
+pub async fn check_layout(root_path: &Path, registry: &Registry) -> Result<(), Error> {
+    for file_name in [VOTE_TEMPLATE, PRE_PUBRING, POST_PUBRING] {
+        let file_path: PathBuf = root_path.join(file_name);
+        if !fs::try_exists(&file_path).await? {
+            return Err(Error::other(format!("missing file: {:?}", file_path)));
+        }
+    }
+
+    // every registered election site gets its own dir with its keyring,
+    // which has to be put there before the server starts
+    let mut missing: Vec<PathBuf> = Vec::new();
+    for site in registry.get_sites() {
+        let site_path: PathBuf = root_path.join(SITES_DIR).join(site.get_site());
+        fs::create_dir_all(&site_path).await?;
+
+        let pubring_path: PathBuf = site_path.join(CLIENT_PUBRING);
+        if !fs::try_exists(&pubring_path).await? {
+            missing.push(pubring_path);
+        }
+    }
+    if !missing.is_empty() {
+        return Err(Error::other(format!("missing keyrings of election sites: {:?}", missing)));
+    }
+    println!("serving files for {} election sites", registry.get_sites().len());
+
+    Ok(())
+}
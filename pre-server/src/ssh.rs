@@ -0,0 +1,170 @@
+use russh::keys::ssh_key::rand_core::OsRng;
+use russh::keys::ssh_key::LineEnding;
+use russh::keys::{Algorithm, PrivateKey, PublicKey};
+use russh::server::{Auth, Config, Handler, Msg, Server as _, Session};
+use russh::{Channel, ChannelId};
+use std::collections::HashMap;
+use std::io::Error;
+use std::net::SocketAddr;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::Duration;
+use vote42::registry::Registry;
+
+use crate::sftp::SftpSession;
+
+// load the host key, or make a new one if there is none yet
+// takes:
+//   path to host key file (&Path)
+// returns:
+//   host key (PrivateKey)
+pub fn get_host_key(host_key_path: &Path) -> Result<PrivateKey, Error> {
+    if host_key_path.exists() {
+        return russh::keys::load_secret_key(host_key_path, None)
+            .map_err(|e| Error::other(format!("failed to read host key: {}", e)));
+    }
+
+    let host_key = PrivateKey::random(&mut OsRng, Algorithm::Ed25519)
+        .map_err(|e| Error::other(format!("failed to make host key: {}", e)))?;
+    host_key
+        .write_openssh_file(host_key_path, LineEnding::LF)
+        .map_err(|e| Error::other(format!("failed to write host key: {}", e)))?;
+    println!("new host key written: {:?}", host_key_path);
+
+    Ok(host_key)
+}
+
+#[derive(Clone)]
+struct Server {
+    root_path: PathBuf,
+    election_path: PathBuf,
+    registry: Arc<Registry>,
+}
+
+impl russh::server::Server for Server {
+    type Handler = SshSession;
+
+    fn new_client(&mut self, peer: Option<SocketAddr>) -> Self::Handler {
+        println!("new connection established: {:?}", peer);
+
+        SshSession {
+            root_path: self.root_path.clone(),
+            election_path: self.election_path.clone(),
+            registry: self.registry.clone(),
+            user: None,
+            channels: HashMap::new(),
+        }
+    }
+}
+
+// one ssh connection. the only thing a client can do is open the sftp
+// subsystem; there are no shells and no commands
+struct SshSession {
+    root_path: PathBuf,
+    election_path: PathBuf,
+    registry: Arc<Registry>,
+    user: Option<String>,
+    channels: HashMap<ChannelId, Channel<Msg>>,
+}
+
+impl Handler for SshSession {
+    type Error = russh::Error;
+
+    async fn auth_publickey(
+        &mut self,
+        user: &str,
+        public_key: &PublicKey,
+    ) -> Result<Auth, Self::Error> {
+        // the user name is the site id and the key has to be the one
+        // registered for that site
+        let is_registered: bool = match self.registry.get_site(user) {
+            Some(site) => match public_key.to_openssh() {
+                Ok(key) => site.has_ssh_key(&key),
+                Err(_) => false,
+            },
+            None => false,
+        };
+
+        if is_registered {
+            println!("{} authenticated", user);
+            self.user = Some(user.to_string());
+            return Ok(Auth::Accept);
+        }
+
+        eprintln!("E: rejected key for {:?}", user);
+        Ok(Auth::Reject {
+            proceed_with_methods: None,
+            partial_success: false,
+        })
+    }
+
+    async fn channel_open_session(
+        &mut self,
+        channel: Channel<Msg>,
+        _session: &mut Session,
+    ) -> Result<bool, Self::Error> {
+        self.channels.insert(channel.id(), channel);
+        Ok(true)
+    }
+
+    async fn channel_eof(
+        &mut self,
+        channel_id: ChannelId,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        session.close(channel_id)?;
+        Ok(())
+    }
+
+    async fn subsystem_request(
+        &mut self,
+        channel_id: ChannelId,
+        name: &str,
+        session: &mut Session,
+    ) -> Result<(), Self::Error> {
+        let (channel, user) = match (self.channels.remove(&channel_id), self.user.clone()) {
+            (Some(c), Some(u)) if name == "sftp" => (c, u),
+            _ => {
+                session.channel_failure(channel_id)?;
+                return Ok(());
+            }
+        };
+
+        session.channel_success(channel_id)?;
+        let sftp = SftpSession::new(self.root_path.clone(), self.election_path.clone(), user);
+        russh_sftp::server::run(channel.into_stream(), sftp).await;
+
+        Ok(())
+    }
+}
+
+// serve the file root read-only over sftp until the process is stopped
+// takes:
+//   address and port to listen on (String)
+//   host key (PrivateKey)
+//   path to file root (PathBuf)
+//   path to election file (PathBuf)
+//   registry of election sites (Registry)
+pub async fn run(
+    addr: String,
+    host_key: PrivateKey,
+    root_path: PathBuf,
+    election_path: PathBuf,
+    registry: Registry,
+) -> Result<(), Error> {
+    let config = Config {
+        keys: vec![host_key],
+        auth_rejection_time: Duration::from_secs(3),
+        auth_rejection_time_initial: Some(Duration::from_secs(0)),
+        inactivity_timeout: Some(Duration::from_secs(600)),
+        ..Default::default()
+    };
+
+    let mut server = Server {
+        root_path,
+        election_path,
+        registry: Arc::new(registry),
+    };
+
+    server.run_on_address(Arc::new(config), addr).await
+}
@@ -0,0 +1,400 @@
+use sequoia_openpgp as openpgp;
+use openpgp::armor;
+use openpgp::cert::{Cert, CertParser};
+use openpgp::crypto::{KeyPair, Password, SessionKey};
+use openpgp::packet::{PKESK, SKESK};
+use openpgp::parse::stream::{
+    DecryptionHelper, DecryptorBuilder, DetachedVerifierBuilder, MessageLayer, MessageStructure,
+    VerificationHelper,
+};
+use openpgp::parse::Parse;
+use openpgp::policy::StandardPolicy;
+use openpgp::serialize::stream::{Armorer, Encryptor2, LiteralWriter, Message, Signer};
+use openpgp::types::SymmetricAlgorithm;
+use openpgp::{Fingerprint, KeyHandle};
+use std::fs;
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+
+// hands the verifier the one certificate a signature has to come from
+struct VerifyHelper {
+    cert: Cert,
+}
+
+impl VerificationHelper for VerifyHelper {
+    fn get_certs(&mut self, _ids: &[KeyHandle]) -> openpgp::Result<Vec<Cert>> {
+        Ok(vec![self.cert.clone()])
+    }
+
+    fn check(&mut self, structure: MessageStructure) -> openpgp::Result<()> {
+        for layer in structure.into_iter() {
+            if let MessageLayer::SignatureGroup { results } = layer {
+                for result in results {
+                    match result {
+                        Ok(_) => return Ok(()),
+                        Err(e) => eprintln!("E: bad signature: {}", e),
+                    }
+                }
+            }
+        }
+
+        Err(openpgp::Error::BadSignature("no valid signature".into()).into())
+    }
+}
+
+// decrypts a message with the post-server keys and remembers who made
+// a good signature while it is verified
+struct DecryptHelper<'a> {
+    keys: Vec<KeyPair>,
+    certs: &'a [Cert],
+    signer: Option<Fingerprint>,
+}
+
+impl DecryptionHelper for DecryptHelper<'_> {
+    fn decrypt<D>(
+        &mut self,
+        pkesks: &[PKESK],
+        _skesks: &[SKESK],
+        sym_algo: Option<SymmetricAlgorithm>,
+        mut decrypt: D,
+    ) -> openpgp::Result<Option<Fingerprint>>
+    where
+        D: FnMut(SymmetricAlgorithm, &SessionKey) -> bool,
+    {
+        for pkesk in pkesks {
+            for keypair in self.keys.iter_mut() {
+                if let Some((algo, session_key)) = pkesk.decrypt(keypair, sym_algo) {
+                    if decrypt(algo, &session_key) {
+                        return Ok(None);
+                    }
+                }
+            }
+        }
+
+        Err(openpgp::Error::MissingSessionKey("not encrypted to the post-server".into()).into())
+    }
+}
+
+impl VerificationHelper for DecryptHelper<'_> {
+    fn get_certs(&mut self, _ids: &[KeyHandle]) -> openpgp::Result<Vec<Cert>> {
+        Ok(self.certs.to_vec())
+    }
+
+    fn check(&mut self, structure: MessageStructure) -> openpgp::Result<()> {
+        // only accept signatures inside the encryption layer
+        let mut encrypted: bool = false;
+
+        for layer in structure.into_iter() {
+            match layer {
+                MessageLayer::Encryption { .. } => encrypted = true,
+                MessageLayer::SignatureGroup { results } if encrypted => {
+                    for result in results {
+                        match result {
+                            Ok(good) => {
+                                self.signer = Some(good.ka.cert().fingerprint());
+                                return Ok(());
+                            }
+                            Err(e) => eprintln!("E: bad signature: {}", e),
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        Err(openpgp::Error::BadSignature("no valid signature from a known key".into()).into())
+    }
+}
+
+// get the key pair used for signing from a secret key file
+// the password is only asked for if the key is encrypted
+// takes:
+//   path to secret key file (&Path)
+//   function asking for the password (FnOnce() -> Result<String, Error>)
+// returns:
+//   signing key pair (KeyPair)
+pub fn get_signing_keypair<F>(secring_path: &Path, password: F) -> Result<KeyPair, Error>
+where
+    F: FnOnce() -> Result<String, Error>,
+{
+    let cert: Cert = Cert::from_file(secring_path).map_err(|e| {
+        Error::Crypto(format!("failed to read secret key {:?}: {}", secring_path, e))
+    })?;
+    println!("signing key: {}", cert.fingerprint());
+
+    let policy = StandardPolicy::new();
+    let mut key = match cert
+        .keys()
+        .secret()
+        .with_policy(&policy, None)
+        .supported()
+        .alive()
+        .revoked(false)
+        .for_signing()
+        .next()
+    {
+        Some(ka) => ka.key().clone(),
+        None => {
+            return Err(Error::Crypto(format!(
+                "no valid signing key in {:?}",
+                secring_path
+            )));
+        }
+    };
+
+    // check if key is password encrypted
+    if key.secret().is_encrypted() {
+        key = key
+            .decrypt_secret(&Password::from(password()?))
+            .map_err(|e| Error::Crypto(format!("failed to decrypt signing key: {}", e)))?;
+    }
+
+    key.into_keypair()
+        .map_err(|e| Error::Crypto(format!("failed to use signing key: {}", e)))
+}
+
+// make a detached, ASCII armored signature over a file
+// the signature is written next to the file as "<file>.sig"
+// takes:
+//   signing key pair (KeyPair)
+//   path to file to sign (&Path)
+// returns:
+//   path to signature file (PathBuf)
+pub fn sign_file(keypair: KeyPair, file_path: &Path) -> Result<PathBuf, Error> {
+    let data: Vec<u8> = fs::read(file_path)
+        .map_err(|e| Error::Io(format!("failed to read {:?}", file_path), e))?;
+
+    // build signature in memory
+    let mut sink: Vec<u8> = Vec::new();
+    let message = Message::new(&mut sink);
+    let message = Armorer::new(message)
+        .kind(armor::Kind::Signature)
+        .build()
+        .map_err(|e| Error::Crypto(format!("failed to create armorer: {}", e)))?;
+    let mut signer = Signer::new(message, keypair)
+        .detached()
+        .build()
+        .map_err(|e| Error::Crypto(format!("failed to create signer: {}", e)))?;
+    signer
+        .write_all(&data)
+        .map_err(|e| Error::Crypto(format!("failed to sign file: {}", e)))?;
+    signer
+        .finalize()
+        .map_err(|e| Error::Crypto(format!("failed to sign file: {}", e)))?;
+
+    let mut sig_path: PathBuf = file_path.to_path_buf();
+    sig_path.as_mut_os_string().push(".sig");
+    fs::write(&sig_path, &sink)
+        .map_err(|e| Error::Io(format!("failed to write {:?}", sig_path), e))?;
+
+    println!("signature written: {:?}", sig_path);
+    Ok(sig_path)
+}
+
+// verify a detached signature over a file
+// the signature is expected next to the file as "<file>.sig"
+// takes:
+//   path to public key file of the signer (&Path)
+//   path to signed file (&Path)
+pub fn verify_file(pubring_path: &Path, file_path: &Path) -> Result<(), Error> {
+    let cert: Cert = Cert::from_file(pubring_path).map_err(|e| {
+        Error::Crypto(format!("failed to read public key {:?}: {}", pubring_path, e))
+    })?;
+    println!("verifying key: {}", cert.fingerprint());
+
+    let mut sig_path: PathBuf = file_path.to_path_buf();
+    sig_path.as_mut_os_string().push(".sig");
+    let sig: Vec<u8> = fs::read(&sig_path)
+        .map_err(|e| Error::Io(format!("failed to read signature {:?}", sig_path), e))?;
+    let data: Vec<u8> = fs::read(file_path)
+        .map_err(|e| Error::Io(format!("failed to read {:?}", file_path), e))?;
+
+    let policy = StandardPolicy::new();
+    let mut verifier = DetachedVerifierBuilder::from_bytes(&sig)
+        .and_then(|b| b.with_policy(&policy, None, VerifyHelper { cert }))
+        .map_err(|e| Error::Crypto(format!("failed to read signature {:?}: {}", sig_path, e)))?;
+
+    verifier.verify_bytes(&data).map_err(|e| {
+        Error::Crypto(format!("signature verification of {:?} failed: {}", file_path, e))
+    })?;
+
+    println!("signature verified: {:?}", file_path);
+    Ok(())
+}
+
+// sign a file and encrypt it to the post-server, so only the
+// post-server can read it and it can tell which election site sent it
+// the result is an ASCII armored OpenPGP message written next to the
+// file as "<file>.pgp"
+// takes:
+//   signing key pair (KeyPair)
+//   path to post-server public key file (&Path)
+//   path to file to seal (&Path)
+// returns:
+//   path to sealed file (PathBuf)
+pub fn seal_file(keypair: KeyPair, post_pubring_path: &Path, file_path: &Path) -> Result<PathBuf, Error> {
+    let post_cert: Cert = Cert::from_file(post_pubring_path).map_err(|e| {
+        Error::Crypto(format!("failed to read post-server key {:?}: {}", post_pubring_path, e))
+    })?;
+    println!("post-server key: {}", post_cert.fingerprint());
+
+    let policy = StandardPolicy::new();
+    let recipients: Vec<_> = post_cert
+        .keys()
+        .with_policy(&policy, None)
+        .supported()
+        .alive()
+        .revoked(false)
+        .for_transport_encryption()
+        .collect();
+    if recipients.is_empty() {
+        return Err(Error::Crypto(format!(
+            "no valid encryption key in {:?}",
+            post_pubring_path
+        )));
+    }
+
+    let data: Vec<u8> = fs::read(file_path)
+        .map_err(|e| Error::Io(format!("failed to read {:?}", file_path), e))?;
+
+    // build message in memory: armor(encrypt(sign(literal data)))
+    let mut sink: Vec<u8> = Vec::new();
+    let message = Message::new(&mut sink);
+    let message = Armorer::new(message)
+        .build()
+        .map_err(|e| Error::Crypto(format!("failed to create armorer: {}", e)))?;
+    let message = Encryptor2::for_recipients(message, recipients)
+        .build()
+        .map_err(|e| Error::Crypto(format!("failed to create encryptor: {}", e)))?;
+    let message = Signer::new(message, keypair)
+        .build()
+        .map_err(|e| Error::Crypto(format!("failed to create signer: {}", e)))?;
+    let mut message = LiteralWriter::new(message)
+        .build()
+        .map_err(|e| Error::Crypto(format!("failed to create literal writer: {}", e)))?;
+    message
+        .write_all(&data)
+        .map_err(|e| Error::Crypto(format!("failed to seal file: {}", e)))?;
+    message
+        .finalize()
+        .map_err(|e| Error::Crypto(format!("failed to seal file: {}", e)))?;
+
+    let mut sealed_path: PathBuf = file_path.to_path_buf();
+    sealed_path.as_mut_os_string().push(".pgp");
+    fs::write(&sealed_path, &sink)
+        .map_err(|e| Error::Io(format!("failed to write {:?}", sealed_path), e))?;
+
+    println!("sealed file written: {:?}", sealed_path);
+    Ok(sealed_path)
+}
+
+// load all certificates from the files in a directory
+// files and certificates that can't be read are skipped and handed back,
+// so the caller decides when to report them
+// takes:
+//   path to key directory (&Path)
+// returns:
+//   certificates (Vec<Cert>) and what was skipped and why (Vec<String>)
+pub fn load_certs(keys_path: &Path) -> Result<(Vec<Cert>, Vec<String>), Error> {
+    let mut certs: Vec<Cert> = Vec::new();
+    let mut skipped: Vec<String> = Vec::new();
+
+    let entries = fs::read_dir(keys_path)
+        .map_err(|e| Error::Io(format!("failed to read key directory {:?}", keys_path), e))?;
+    for entry in entries {
+        let file_path = entry?.path();
+        if !file_path.is_file() {
+            continue;
+        }
+
+        // a file may hold a single certificate or a whole keyring
+        let parser = match CertParser::from_file(&file_path) {
+            Ok(p) => p,
+            Err(e) => {
+                skipped.push(format!("failed to read keyring {:?}: {}", file_path, e));
+                continue;
+            }
+        };
+        for cert in parser {
+            match cert {
+                Ok(c) => certs.push(c),
+                Err(e) => skipped.push(format!("bad certificate in {:?}: {}", file_path, e)),
+            }
+        }
+    }
+
+    Ok((certs, skipped))
+}
+
+// load the unencrypted secret keys used to decrypt submissions
+// takes:
+//   path to secret key file (&Path)
+// returns:
+//   decryption key pairs (Vec<KeyPair>)
+pub fn load_keypairs(secring_path: &Path) -> Result<Vec<KeyPair>, Error> {
+    let cert: Cert = Cert::from_file(secring_path).map_err(|e| {
+        Error::Crypto(format!("failed to read secret key {:?}: {}", secring_path, e))
+    })?;
+
+    let policy = StandardPolicy::new();
+    let mut keys: Vec<KeyPair> = Vec::new();
+    for ka in cert
+        .keys()
+        .unencrypted_secret()
+        .with_policy(&policy, None)
+        .supported()
+        .for_transport_encryption()
+    {
+        let keypair: KeyPair = ka.key().clone().into_keypair().map_err(|e| {
+            Error::Crypto(format!("failed to use decryption key: {}", e))
+        })?;
+        keys.push(keypair);
+    }
+
+    if keys.is_empty() {
+        return Err(Error::Crypto(format!(
+            "no unencrypted decryption key in {:?}",
+            secring_path
+        )));
+    }
+
+    Ok(keys)
+}
+
+// decrypt a submitted message and verify its signature
+// takes:
+//   post-server decryption keys (&[KeyPair])
+//   certificates allowed to sign (&[Cert])
+//   encrypted message (&[u8])
+// returns:
+//   decrypted contents (Vec<u8>) and fingerprint of the signing
+//   certificate (Fingerprint) or the reason it failed (String)
+pub fn decrypt_verify(
+    keys: &[KeyPair],
+    certs: &[Cert],
+    message: &[u8],
+) -> Result<(Vec<u8>, Fingerprint), String> {
+    let policy = StandardPolicy::new();
+    let helper = DecryptHelper {
+        keys: keys.to_vec(),
+        certs,
+        signer: None,
+    };
+
+    let mut decryptor = DecryptorBuilder::from_bytes(message)
+        .and_then(|b| b.with_policy(&policy, None, helper))
+        .map_err(|e| format!("failed to decrypt message: {}", e))?;
+
+    // the signature is checked once all data has been read
+    let mut data: Vec<u8> = Vec::new();
+    decryptor
+        .read_to_end(&mut data)
+        .map_err(|e| format!("failed to decrypt message: {}", e))?;
+
+    match decryptor.into_helper().signer {
+        Some(fingerprint) => Ok((data, fingerprint)),
+        None => Err("signature verification failed".to_string()),
+    }
+}
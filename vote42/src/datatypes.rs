@@ -0,0 +1,774 @@
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+use crate::template::check_names;
+
+pub const SCHEMA_VERSION: u32 = 2; // version of the vote template and result format
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Color(u8, u8, u8);
+
+// a candidate on a party's list
+// votes are only counted per candidate if the template asks for it
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Candidate {
+    name: String,
+    #[serde(default)]
+    votes: u32,
+}
+
+impl Candidate {
+    // get candidate name
+    // takes:
+    //   reference to candidate (&Candidate)
+    // returns:
+    //   candidate name (String)
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    // get candidate votes
+    // takes:
+    //   reference to candidate (&Candidate)
+    // returns:
+    //   candidate votes (u32)
+    pub fn get_votes(&self) -> u32 {
+        self.votes
+    }
+
+    // set the votes of the candidate
+    // takes:
+    //   num of votes (u32)
+    //   mutable reference to candidate (&mut Candidate)
+    pub fn set_votes(&mut self, votes: u32) {
+        self.votes = votes;
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Party {
+    // stable id the post-server aggregates on, e.g. ballot position and
+    // short name; it stays the same when a template revision reorders or
+    // renames parties
+    id: String,
+    name: String,
+    long_name: String,
+    color: Color,
+    candidates: Vec<Candidate>,
+    votes: u32,
+    // share of the valid votes in the previous election in percent
+    #[serde(default)]
+    previous_share: Option<f64>,
+}
+
+impl Party {
+    // set the votes of the party
+    // takes:
+    //   num of votes (u32)
+    //   mutable reference to party struct (&mut Party)
+    pub fn set_votes(&mut self, votes: u32) {
+        self.votes = votes;
+    }
+
+    // get party id
+    // takes:
+    //   reference to party (&Party)
+    // returns:
+    //   party id (String)
+    pub fn get_id(&self) -> String {
+        self.id.clone()
+    }
+
+    // get party name
+    // takes:
+    //   party (Party)
+    // returns:
+    //   party name (String)
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    // get party long name
+    // takes:
+    //   party (Party)
+    // returns:
+    //   party long name (String)
+    pub fn get_long_name(&self) -> String {
+        self.long_name.clone()
+    }
+
+    // get party color
+    // takes:
+    //   party (Party)
+    // returns:
+    //   party color (Color)
+    pub fn get_color(&self) -> Color {
+        self.color.clone()
+    }
+
+    // get party candidates
+    // takes:
+    //   reference to party (&Party)
+    // returns:
+    //   reference to candidates vec (&Vec<Candidate>)
+    pub fn get_candidates(&self) -> &Vec<Candidate> {
+        &self.candidates
+    }
+
+    // get mutable reference to party candidates
+    // takes:
+    //   mutable reference to party (&mut Party)
+    // returns:
+    //   mutable reference to candidates vec (&mut Vec<Candidate>)
+    pub fn get_mut_candidates(&mut self) -> &mut Vec<Candidate> {
+        &mut self.candidates
+    }
+
+    // set the party votes to the sum of its candidates' votes
+    // takes:
+    //   mutable reference to party (&mut Party)
+    pub fn set_votes_from_candidates(&mut self) {
+        self.votes = self
+            .candidates
+            .iter()
+            .fold(0, |sum: u32, c| sum.saturating_add(c.votes));
+    }
+
+    // get party votes
+    // takes:
+    //   to party (Party)
+    // returns:
+    //   party votes (u32)
+    pub fn get_votes(&self) -> u32 {
+        self.votes
+    }
+
+    // get party share in the previous election
+    // takes:
+    //   reference to party (&Party)
+    // returns:
+    //   share in percent, if known (Option<f64>)
+    pub fn get_previous_share(&self) -> Option<f64> {
+        self.previous_share
+    }
+}
+
+// what is known about the electorate of an election site
+// as reported on the official results form
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Electorate {
+    registered_voters: u32, // voters on the register
+    #[serde(default)]
+    voters: u32,            // voters who actually voted
+    ballots_cast: u32,      // ballots found in the ballot box
+    invalid_ballots: u32,
+    #[serde(default)]
+    blank_ballots: u32,     // valid ballots without a vote for any party
+}
+
+impl Electorate {
+    // get number of voters on the register
+    // takes:
+    //   reference to electorate (&Electorate)
+    // returns:
+    //   registered voters (u32)
+    pub fn get_registered_voters(&self) -> u32 {
+        self.registered_voters
+    }
+
+    // get number of voters who actually voted
+    // takes:
+    //   reference to electorate (&Electorate)
+    // returns:
+    //   voters (u32)
+    pub fn get_voters(&self) -> u32 {
+        self.voters
+    }
+
+    // get number of ballots cast
+    // takes:
+    //   reference to electorate (&Electorate)
+    // returns:
+    //   ballots cast (u32)
+    pub fn get_ballots_cast(&self) -> u32 {
+        self.ballots_cast
+    }
+
+    // get number of invalid ballots
+    // takes:
+    //   reference to electorate (&Electorate)
+    // returns:
+    //   invalid ballots (u32)
+    pub fn get_invalid_ballots(&self) -> u32 {
+        self.invalid_ballots
+    }
+
+    // get number of blank ballots
+    // takes:
+    //   reference to electorate (&Electorate)
+    // returns:
+    //   blank ballots (u32)
+    pub fn get_blank_ballots(&self) -> u32 {
+        self.blank_ballots
+    }
+
+    // set number of voters on the register
+    // takes:
+    //   num of voters (u32)
+    //   mutable reference to electorate (&mut Electorate)
+    pub fn set_registered_voters(&mut self, registered_voters: u32) {
+        self.registered_voters = registered_voters;
+    }
+
+    // set number of voters who actually voted
+    // takes:
+    //   num of voters (u32)
+    //   mutable reference to electorate (&mut Electorate)
+    pub fn set_voters(&mut self, voters: u32) {
+        self.voters = voters;
+    }
+
+    // set number of ballots cast
+    // takes:
+    //   num of ballots (u32)
+    //   mutable reference to electorate (&mut Electorate)
+    pub fn set_ballots_cast(&mut self, ballots_cast: u32) {
+        self.ballots_cast = ballots_cast;
+    }
+
+    // set number of invalid ballots
+    // takes:
+    //   num of ballots (u32)
+    //   mutable reference to electorate (&mut Electorate)
+    pub fn set_invalid_ballots(&mut self, invalid_ballots: u32) {
+        self.invalid_ballots = invalid_ballots;
+    }
+
+    // set number of blank ballots
+    // takes:
+    //   num of ballots (u32)
+    //   mutable reference to electorate (&mut Electorate)
+    pub fn set_blank_ballots(&mut self, blank_ballots: u32) {
+        self.blank_ballots = blank_ballots;
+    }
+}
+
+// one contest of an election, e.g. the first or the second vote, with
+// its own options and its own counts
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Contest {
+    name: String,
+    // electorate data, if the template has it
+    #[serde(default)]
+    electorate: Option<Electorate>,
+    // votes every voter has; more than one with kumulieren/panaschieren
+    #[serde(default = "default_votes_per_ballot")]
+    votes_per_ballot: u32,
+    // votes are counted per candidate and party votes derived from them
+    #[serde(default)]
+    candidate_votes: bool,
+    parties: Vec<Party>,
+}
+
+// templates without votes_per_ballot give every voter one vote
+// returns:
+//   votes per ballot (u32)
+fn default_votes_per_ballot() -> u32 {
+    1
+}
+
+impl Contest {
+    // get contest name
+    // takes:
+    //   reference to contest (&Contest)
+    // returns:
+    //   contest name (String)
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    // get contest electorate data
+    // takes:
+    //   reference to contest (&Contest)
+    // returns:
+    //   electorate data, if there is any (Option<&Electorate>)
+    pub fn get_electorate(&self) -> Option<&Electorate> {
+        self.electorate.as_ref()
+    }
+
+    // get votes every voter has
+    // takes:
+    //   reference to contest (&Contest)
+    // returns:
+    //   votes per ballot (u32)
+    pub fn get_votes_per_ballot(&self) -> u32 {
+        self.votes_per_ballot
+    }
+
+    // check if votes are counted per candidate
+    // takes:
+    //   reference to contest (&Contest)
+    // returns:
+    //   hasCandidateVotes (bool)
+    pub fn has_candidate_votes(&self) -> bool {
+        self.candidate_votes
+    }
+
+    // get mutable reference to contest electorate data
+    // a template without electorate data gets an empty one
+    // takes:
+    //   mutable reference to contest (&mut Contest)
+    // returns:
+    //   mutable reference to electorate data (&mut Electorate)
+    pub fn get_mut_electorate(&mut self) -> &mut Electorate {
+        self.electorate.get_or_insert_with(Electorate::default)
+    }
+
+    // get contest parties
+    // takes:
+    //   reference to contest (&Contest)
+    // returns:
+    //   reference to parties vec (&Vec<Party>)
+    pub fn get_parties(&self) -> &Vec<Party> {
+        &self.parties
+    }
+
+    // get mutable reference to contest parties
+    // takes:
+    //   mutable reference to contest (&mut Contest)
+    // returns:
+    //   mutable reference to parties vec (&mut Vec<Party>)
+    pub fn get_mut_parties(&mut self) -> &mut Vec<Party> {
+        &mut self.parties
+    }
+
+    // find everything that keeps a contest from being counted: no options,
+    // and options without a name or id or listed twice
+    // takes:
+    //   reference to contest (&Contest)
+    // returns:
+    //   problems found (Vec<String>)
+    pub fn check_options(&self) -> Vec<String> {
+        let mut problems: Vec<String> = Vec::new();
+
+        if self.parties.is_empty() {
+            problems.push("contest contains no parties".to_string());
+        }
+
+        let names: Vec<String> = self.parties.iter().map(|p| p.name.clone()).collect();
+        problems.append(&mut check_names("party", &names));
+
+        // results are counted by id on the post-server
+        let ids: Vec<String> = self.parties.iter().map(|p| p.id.clone()).collect();
+        problems.append(&mut check_names("party id", &ids));
+
+        problems
+    }
+
+    // check that a contest has options and no option twice
+    // takes:
+    //   reference to contest (&Contest)
+    // returns:
+    //   nothing or a description of the first problem found (String)
+    fn validate(&self) -> Result<(), String> {
+        match self.check_options().into_iter().next() {
+            Some(problem) => Err(problem),
+            None => Ok(()),
+        }
+    }
+
+    // check that a contest of a result has the options of the same contest
+    // of the published template, identified by their ids
+    // takes:
+    //   reference to contest (&Contest)
+    //   reference to contest of the template (&Contest)
+    // returns:
+    //   nothing or a description of the first difference found (String)
+    fn matches_template(&self, template: &Contest) -> Result<(), String> {
+        // both change what the plausibility checks accept
+        if self.votes_per_ballot != template.votes_per_ballot {
+            return Err(format!(
+                "votes_per_ballot is {}, the template has {}",
+                self.votes_per_ballot, template.votes_per_ballot
+            ));
+        }
+        if self.candidate_votes != template.candidate_votes {
+            return Err(format!(
+                "candidate_votes is {}, the template has {}",
+                self.candidate_votes, template.candidate_votes
+            ));
+        }
+
+        for party in &self.parties {
+            let template_party: &Party = template
+                .parties
+                .iter()
+                .find(|p| p.id == party.id)
+                .ok_or(format!("party id {} is not in the template", party.id))?;
+
+            let candidates: Vec<&String> = party.candidates.iter().map(|c| &c.name).collect();
+            let template_candidates: Vec<&String> =
+                template_party.candidates.iter().map(|c| &c.name).collect();
+            if candidates != template_candidates {
+                return Err(format!(
+                    "candidates of party {} differ from the template",
+                    party.id
+                ));
+            }
+        }
+
+        for template_party in &template.parties {
+            if !self.parties.iter().any(|p| p.id == template_party.id) {
+                return Err(format!("party id {} is missing", template_party.id));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Vote {
+    schema_version: u32,
+    // election the template was published for
+    election_id: String,
+    // nonce the pre-server issued to the site for the election; empty in
+    // the template, set by the client
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    nonce: String,
+    election_site: String,
+    election_admin: String,
+    datetime: String,
+    // 1 for the first result of a site, counted up with every correction;
+    // the post-server counts the highest revision it has received
+    #[serde(default = "default_revision")]
+    revision: u32,
+    // why the result was corrected, e.g. a recount
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    reason: Option<String>,
+    // everything voted on, entered and sent together
+    contests: Vec<Contest>,
+    // operators who entered the counts, if they were entered twice
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    entered_by: Vec<String>,
+}
+
+// results without a revision are first results
+// returns:
+//   revision (u32)
+fn default_revision() -> u32 {
+    1
+}
+
+impl Vote {
+    // popoulate Vote struct from JSON file
+    // takes:
+    //   path to JSON file (&Path)
+    // returns:
+    //   vote Struct (Vote)
+    pub fn create_from_json(json_file_path: &Path) -> Result<Self, Error> {
+        let file = File::open(json_file_path).map_err(|e| {
+            Error::Io(format!("failed to open vote template file {:?}", json_file_path), e)
+        })?;
+
+        let reader = BufReader::new(file);
+
+        let vote: Vote = serde_json::from_reader(reader).map_err(|e| {
+            Error::Json(format!("failed to parse vote template {:?}", json_file_path), e)
+        })?;
+
+        if vote.schema_version != SCHEMA_VERSION {
+            return Err(Error::Validation(format!(
+                "vote template has schema version {}, expected {}",
+                vote.schema_version, SCHEMA_VERSION
+            )));
+        }
+
+        Ok(vote)
+    }
+
+    // popoulate Vote struct from the raw bytes of a submitted result file
+    // takes:
+    //   file contents (&[u8])
+    // returns:
+    //   vote struct (Vote)
+    pub fn from_slice(data: &[u8]) -> serde_json::Result<Self> {
+        serde_json::from_slice(data)
+    }
+
+    // write to JSON file
+    // takes:
+    //   path to JSON file
+    // returns:
+    //   path the file was written to (PathBuf)
+    pub fn write_to_json(&self, mut json_file_path: PathBuf) -> Result<PathBuf, Error> {
+
+        if json_file_path.extension().is_none() {
+            json_file_path.set_extension("json");
+        }
+
+        println!(
+            "attempting to write Vote to JSON file: {:?}",
+            json_file_path
+        );
+        let mut file = File::create(json_file_path.clone()).map_err(|e| {
+            Error::Io(format!("failed to create vote file {:?}", json_file_path), e)
+        })?;
+
+        let json_string = serde_json::to_string_pretty(&self)
+            .map_err(|e| Error::Json("failed to serialize vote".to_string(), e))?;
+
+        file.write_all(json_string.as_bytes()).map_err(|e| {
+            Error::Io(format!("failed to write JSON data to {:?}", json_file_path), e)
+        })?;
+
+        Ok(json_file_path)
+    }
+
+    // get id of the election
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   election id (String)
+    pub fn get_election_id(&self) -> String {
+        self.election_id.clone()
+    }
+
+    // get nonce issued to the election site
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   nonce (String)
+    pub fn get_nonce(&self) -> String {
+        self.nonce.clone()
+    }
+
+    // get vote election site name
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   election site name (String)
+    pub fn get_election_site(&self) -> String {
+        self.election_site.clone()
+    }
+
+    // get vote election admin name
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   election admin name (String)
+    pub fn get_election_admin(&self) -> String {
+        self.election_admin.clone()
+    }
+
+    // get vote datetime
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   datetime (String)
+    pub fn get_datetime(&self) -> String {
+        self.datetime.clone()
+    }
+
+    // get vote revision
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   revision (u32)
+    pub fn get_revision(&self) -> u32 {
+        self.revision
+    }
+
+    // get reason for the correction
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   reason, if the vote is a correction (Option<String>)
+    pub fn get_reason(&self) -> Option<String> {
+        self.reason.clone()
+    }
+
+    // get vote contests
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   reference to contests vec (&Vec<Contest>)
+    pub fn get_contests(&self) -> &Vec<Contest> {
+        &self.contests
+    }
+
+    // get mutable reference to vote contests
+    // takes:
+    //   mutable reference to vote (&mut Vote)
+    // returns:
+    //   mutable reference to contests vec (&mut Vec<Contest>)
+    pub fn get_mut_contests(&mut self) -> &mut Vec<Contest> {
+        &mut self.contests
+    }
+
+    // set the election site name
+    // takes:
+    //   name of site (String)
+    //   mutable reference to vote struct (&mut Vote)
+    pub fn set_election_site(&mut self, name: String) {
+        self.election_site = name;
+    }
+
+    // set the election admin name
+    // takes:
+    //   name of site (String)
+    //   mutable reference to vote struct (&mut Vote)
+    pub fn set_election_admin(&mut self, name: String) {
+        self.election_admin = name;
+    }
+
+    // set the operators who entered the counts
+    // takes:
+    //   names of operators (Vec<String>)
+    //   mutable reference to vote struct (&mut Vote)
+    pub fn set_entered_by(&mut self, operators: Vec<String>) {
+        self.entered_by = operators;
+    }
+
+    // set the nonce issued to the election site
+    // takes:
+    //   nonce (String)
+    //   mutable reference to vote struct (&mut Vote)
+    pub fn set_nonce(&mut self, nonce: String) {
+        self.nonce = nonce;
+    }
+
+    // set the revision and the reason for a correction
+    // takes:
+    //   revision (u32)
+    //   reason for the correction, none for a first result (Option<String>)
+    //   mutable reference to vote struct (&mut Vote)
+    pub fn set_revision(&mut self, revision: u32, reason: Option<String>) {
+        self.revision = revision;
+        self.reason = reason;
+    }
+
+    // set the votes datetime to the current local time
+    // using the RFC3339 format
+    // takes:
+    //   mutable reference to vote struct (&mut Vote)
+    pub fn set_datetime(&mut self) {
+        self.datetime = Local::now().to_rfc3339();
+    }
+
+    // check that a submitted vote is complete and can be stored
+    // takes:
+    //   reference to vote (&Vote)
+    // returns:
+    //   nothing or a description of the first problem found (String)
+    pub fn validate(&self) -> Result<(), String> {
+        if self.schema_version != SCHEMA_VERSION {
+            return Err(format!(
+                "schema version {} is not supported, expected {}",
+                self.schema_version, SCHEMA_VERSION
+            ));
+        }
+
+        if self.election_id.trim().is_empty() {
+            return Err("election_id is empty".to_string());
+        }
+
+        if self.election_site.trim().is_empty() {
+            return Err("election_site is empty".to_string());
+        }
+
+        // the site name ends up as a directory name
+        if self.election_site.contains(['/', '\\'])
+            || self.election_site.starts_with('.')
+        {
+            return Err(format!(
+                "election_site is not a valid site name: {:?}",
+                self.election_site
+            ));
+        }
+
+        if self.election_admin.trim().is_empty() {
+            return Err("election_admin is empty".to_string());
+        }
+
+        if let Err(e) = DateTime::parse_from_rfc3339(&self.datetime) {
+            return Err(format!(
+                "datetime is not RFC3339: {:?}: {}",
+                self.datetime, e
+            ));
+        }
+
+        // a correction has to say why it was made
+        match (self.revision, &self.reason) {
+            (0, _) => return Err("revision must be at least 1".to_string()),
+            (1, Some(_)) => return Err("revision 1 is no correction but has a reason".to_string()),
+            (1, None) => {}
+            (_, Some(reason)) if !reason.trim().is_empty() => {}
+            (r, _) => return Err(format!("revision {} is a correction without a reason", r)),
+        };
+
+        if self.contests.is_empty() {
+            return Err("vote contains no contests".to_string());
+        }
+
+        let names: Vec<String> = self.contests.iter().map(|c| c.name.clone()).collect();
+        if let Some(problem) = check_names("contest", &names).into_iter().next() {
+            return Err(problem);
+        }
+
+        for contest in &self.contests {
+            contest
+                .validate()
+                .map_err(|e| format!("{}: {}", contest.name, e))?;
+        }
+
+        Ok(())
+    }
+
+    // check that a validated vote is for the election of the published
+    // template and has its contests and options, so counts can't end up with the wrong party when
+    // a template revision reorders or renames them
+    // takes:
+    //   reference to vote (&Vote)
+    //   reference to template (&Vote)
+    // returns:
+    //   nothing or a description of the first difference found (String)
+    pub fn matches_template(&self, template: &Vote) -> Result<(), String> {
+        // a result of another election is never counted
+        if self.election_id != template.election_id {
+            return Err(format!(
+                "result is for election {:?}, the template for {:?}",
+                self.election_id, template.election_id
+            ));
+        }
+
+        for contest in &self.contests {
+            let template_contest: &Contest = template
+                .contests
+                .iter()
+                .find(|c| c.name == contest.name)
+                .ok_or(format!("contest {} is not in the template", contest.name))?;
+
+            contest
+                .matches_template(template_contest)
+                .map_err(|e| format!("{}: {}", contest.name, e))?;
+        }
+
+        for template_contest in &template.contests {
+            if !self.contests.iter().any(|c| c.name == template_contest.name) {
+                return Err(format!("contest {} is missing", template_contest.name));
+            }
+        }
+
+        Ok(())
+    }
+}
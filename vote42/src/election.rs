@@ -0,0 +1,244 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs::{self, File};
+use std::io::{BufReader, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::datatypes::Vote;
+use crate::error::Error;
+
+// where an election is in its life
+// it only ever moves forward, one state after the other
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum State {
+    Draft,     // the template is being prepared, nothing is handed out
+    Published, // the template is handed out, no results are accepted yet
+    Accepting, // the template is handed out and results are accepted
+    Closed,    // no more results are accepted, the totals are checked
+    Certified, // the totals are the official result
+}
+
+const STATES: [State; 5] = [
+    State::Draft,
+    State::Published,
+    State::Accepting,
+    State::Closed,
+    State::Certified,
+];
+
+impl State {
+    // get the state that follows
+    // takes:
+    //   state (State)
+    // returns:
+    //   next state, none once certified (Option<State>)
+    fn next(self) -> Option<State> {
+        let i: usize = STATES.iter().position(|s| *s == self)?;
+        STATES.get(i + 1).copied()
+    }
+
+    // check if election sites may download the template
+    // takes:
+    //   state (State)
+    // returns:
+    //   allowsTemplate (bool)
+    pub fn allows_template(self) -> bool {
+        matches!(self, State::Published | State::Accepting)
+    }
+
+    // check if results are accepted
+    // takes:
+    //   state (State)
+    // returns:
+    //   acceptsResults (bool)
+    pub fn accepts_results(self) -> bool {
+        self == State::Accepting
+    }
+}
+
+impl fmt::Display for State {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name: &str = match self {
+            State::Draft => "draft",
+            State::Published => "published",
+            State::Accepting => "accepting",
+            State::Closed => "closed",
+            State::Certified => "certified",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for State {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        STATES
+            .iter()
+            .find(|state| state.to_string() == s)
+            .copied()
+            .ok_or(format!(
+                "unknown state {:?}, expected one of draft, published, accepting, closed, certified",
+                s
+            ))
+    }
+}
+
+// a state the election was moved to and when
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Transition {
+    state: State,
+    time: String,
+}
+
+// the election a server works for and its state
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Election {
+    election_id: String,
+    state: State,
+    // every state the election was in, the first one being draft
+    history: Vec<Transition>,
+}
+
+impl Election {
+    // popoulate Election struct from JSON file
+    // takes:
+    //   path to JSON file (&Path)
+    // returns:
+    //   election struct (Election)
+    pub fn create_from_json(json_file_path: &Path) -> Result<Self, Error> {
+        let file = File::open(json_file_path).map_err(|e| {
+            Error::Io(format!("failed to open election file {:?}", json_file_path), e)
+        })?;
+
+        serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+            Error::Json(format!("failed to parse election {:?}", json_file_path), e)
+        })
+    }
+
+    // read the election of a server, or start it as a draft if there is none
+    // takes:
+    //   path to JSON file (&Path)
+    //   id of the election of the vote template (&str)
+    // returns:
+    //   election struct (Election)
+    pub fn load_or_create(json_file_path: &Path, election_id: &str) -> Result<Self, Error> {
+        let election: Election = match Self::create_from_json(json_file_path) {
+            Ok(e) => e,
+            Err(Error::Io(_, e)) if e.kind() == ErrorKind::NotFound => {
+                let election = Election {
+                    election_id: election_id.to_string(),
+                    state: State::Draft,
+                    history: vec![Transition {
+                        state: State::Draft,
+                        time: Utc::now().to_rfc3339(),
+                    }],
+                };
+                election.write_to_json(json_file_path)?;
+                println!("election {} started as draft: {:?}", election_id, json_file_path);
+                election
+            }
+            Err(e) => return Err(e),
+        };
+
+        // a new election starts from a new file, so the old one is kept
+        if election.election_id != election_id {
+            return Err(Error::Validation(format!(
+                "{:?} is for election {}, the vote template for {}; move it away to start a new election",
+                json_file_path, election.election_id, election_id
+            )));
+        }
+
+        Ok(election)
+    }
+
+    // write to JSON file
+    // the file is replaced at once, so a server reading it never sees
+    // half of it
+    // takes:
+    //   reference to election (&Election)
+    //   path to JSON file (&Path)
+    pub fn write_to_json(&self, json_file_path: &Path) -> Result<(), Error> {
+        let json_string = serde_json::to_string_pretty(self)
+            .map_err(|e| Error::Json("failed to serialize election".to_string(), e))?;
+
+        let tmp_path: PathBuf = json_file_path.with_extension("tmp");
+        let mut file = File::create(&tmp_path)
+            .map_err(|e| Error::Io(format!("failed to create {:?}", tmp_path), e))?;
+        file.write_all(json_string.as_bytes())
+            .map_err(|e| Error::Io(format!("failed to write JSON data to {:?}", tmp_path), e))?;
+
+        fs::rename(&tmp_path, json_file_path)
+            .map_err(|e| Error::Io(format!("failed to replace {:?}", json_file_path), e))?;
+
+        Ok(())
+    }
+
+    // get election id
+    // takes:
+    //   reference to election (&Election)
+    // returns:
+    //   election id (String)
+    pub fn get_election_id(&self) -> String {
+        self.election_id.clone()
+    }
+
+    // get state of the election
+    // takes:
+    //   reference to election (&Election)
+    // returns:
+    //   state (State)
+    pub fn get_state(&self) -> State {
+        self.state
+    }
+
+    // move the election to the next state
+    // takes:
+    //   mutable reference to election (&mut Election)
+    //   state to move to (State)
+    pub fn transition(&mut self, state: State) -> Result<(), Error> {
+        if self.state.next() != Some(state) {
+            return Err(Error::Validation(format!(
+                "election {} can't go from {} to {}",
+                self.election_id, self.state, state
+            )));
+        }
+
+        self.state = state;
+        self.history.push(Transition {
+            state,
+            time: Utc::now().to_rfc3339(),
+        });
+
+        Ok(())
+    }
+
+    // read the election of the vote template of a server and move it to
+    // the next state, if one is given
+    // takes:
+    //   path to election file (&Path)
+    //   path to vote template (&Path)
+    //   state to move to (Option<State>)
+    // returns:
+    //   election as it is now (Election)
+    pub fn change_state(
+        json_file_path: &Path,
+        vote_template_path: &Path,
+        state: Option<State>,
+    ) -> Result<Self, Error> {
+        let vote: Vote = Vote::create_from_json(vote_template_path)?;
+        let mut election: Election = Self::load_or_create(json_file_path, &vote.get_election_id())?;
+
+        if let Some(state) = state {
+            election.transition(state)?;
+            election.write_to_json(json_file_path)?;
+        }
+
+        Ok(election)
+    }
+}
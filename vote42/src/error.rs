@@ -0,0 +1,55 @@
+use std::fmt;
+use std::io;
+
+// everything that can go wrong in the shared code
+// the binaries map these onto their own errors
+#[derive(Debug)]
+pub enum Error {
+    Io(String, io::Error),           // reading or writing files
+    Json(String, serde_json::Error), // parsing or writing JSON files
+    Validation(String),              // data that is not acceptable
+    Crypto(String),                  // signing, encrypting, decrypting and verifying with OpenPGP
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::Io(context, e) => write!(f, "{}: {}", context, e),
+            Error::Json(context, e) => write!(f, "{}: {}", context, e),
+            Error::Validation(msg) => write!(f, "invalid input: {}", msg),
+            Error::Crypto(msg) => write!(f, "OpenPGP: {}", msg),
+        }
+    }
+}
+
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Io(_, e) => Some(e),
+            Error::Json(_, e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for Error {
+    fn from(e: io::Error) -> Self {
+        Error::Io("I/O error".to_string(), e)
+    }
+}
+
+impl From<serde_json::Error> for Error {
+    fn from(e: serde_json::Error) -> Self {
+        Error::Json("JSON error".to_string(), e)
+    }
+}
+
+// the servers report everything as io::Error
+impl From<Error> for io::Error {
+    fn from(e: Error) -> Self {
+        match e {
+            Error::Io(context, e) => io::Error::new(e.kind(), format!("{}: {}", context, e)),
+            e => io::Error::other(e.to_string()),
+        }
+    }
+}
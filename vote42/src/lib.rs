@@ -0,0 +1,11 @@
+// data model, validation and crypto shared by the client, the pre-server
+// and the post-server, so all of them read and write the same format
+pub mod crypto;
+pub mod datatypes;
+pub mod election;
+pub mod error;
+pub mod nonces;
+pub mod registry;
+pub mod seats;
+pub mod template;
+pub mod validation;
@@ -0,0 +1,188 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{BufReader, Write};
+use std::path::{Path, PathBuf};
+
+use crate::error::Error;
+
+// nonces the pre-server issued to the election sites of an election
+// the post-server only accepts a result carrying the nonce issued to its
+// site for the open election, so a result of another election can't be
+// replayed
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Nonces {
+    election_id: String,
+    // nonce per site id
+    sites: BTreeMap<String, String>,
+}
+
+impl Nonces {
+    // make an empty set of nonces for an election
+    // takes:
+    //   election id (String)
+    // returns:
+    //   nonces (Nonces)
+    pub fn new(election_id: String) -> Self {
+        Nonces {
+            election_id,
+            sites: BTreeMap::new(),
+        }
+    }
+
+    // popoulate Nonces struct from JSON file
+    // takes:
+    //   path to JSON file (&Path)
+    // returns:
+    //   nonces struct (Nonces)
+    pub fn create_from_json(json_file_path: &Path) -> Result<Self, Error> {
+        let file = File::open(json_file_path)
+            .map_err(|e| Error::Io(format!("failed to open nonces file {:?}", json_file_path), e))?;
+
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| Error::Json(format!("failed to parse nonces {:?}", json_file_path), e))
+    }
+
+    // write to JSON file
+    // takes:
+    //   reference to nonces (&Nonces)
+    //   path to JSON file (&Path)
+    pub fn write_to_json(&self, json_file_path: &Path) -> Result<(), Error> {
+        write_json(self, "nonces", json_file_path)
+    }
+
+    // get id of the election the nonces were issued for
+    // takes:
+    //   reference to nonces (&Nonces)
+    // returns:
+    //   election id (String)
+    pub fn get_election_id(&self) -> String {
+        self.election_id.clone()
+    }
+
+    // get nonce issued to an election site
+    // takes:
+    //   reference to nonces (&Nonces)
+    //   site id (&str)
+    // returns:
+    //   nonce, if one was issued (Option<String>)
+    pub fn get(&self, site: &str) -> Option<String> {
+        self.sites.get(site).cloned()
+    }
+
+    // get number of sites nonces were issued to
+    // takes:
+    //   reference to nonces (&Nonces)
+    // returns:
+    //   number of sites (usize)
+    pub fn get_sites_count(&self) -> usize {
+        self.sites.len()
+    }
+
+    // set nonce of an election site
+    // takes:
+    //   mutable reference to nonces (&mut Nonces)
+    //   site id (String)
+    //   nonce (String)
+    pub fn insert(&mut self, site: String, nonce: String) {
+        self.sites.insert(site, nonce);
+    }
+
+    // get nonce of an election site as it is handed out to it
+    // takes:
+    //   reference to nonces (&Nonces)
+    //   site id (&str)
+    // returns:
+    //   nonce of site, if one was issued (Option<SiteNonce>)
+    pub fn for_site(&self, site: &str) -> Option<SiteNonce> {
+        self.get(site).map(|nonce| SiteNonce {
+            election_id: self.election_id.clone(),
+            site: site.to_string(),
+            nonce,
+        })
+    }
+}
+
+// the nonce of one election site, as the client downloads it from the
+// pre-server and puts it into its results
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SiteNonce {
+    election_id: String,
+    site: String,
+    nonce: String,
+}
+
+impl SiteNonce {
+    // popoulate SiteNonce struct from JSON file
+    // takes:
+    //   path to JSON file (&Path)
+    // returns:
+    //   nonce of site (SiteNonce)
+    pub fn create_from_json(json_file_path: &Path) -> Result<Self, Error> {
+        let file = File::open(json_file_path)
+            .map_err(|e| Error::Io(format!("failed to open nonce file {:?}", json_file_path), e))?;
+
+        serde_json::from_reader(BufReader::new(file))
+            .map_err(|e| Error::Json(format!("failed to parse nonce {:?}", json_file_path), e))
+    }
+
+    // write to JSON file
+    // takes:
+    //   reference to nonce of site (&SiteNonce)
+    //   path to JSON file (&Path)
+    pub fn write_to_json(&self, json_file_path: &Path) -> Result<(), Error> {
+        write_json(self, "nonce", json_file_path)
+    }
+
+    // get id of the election the nonce was issued for
+    // takes:
+    //   reference to nonce of site (&SiteNonce)
+    // returns:
+    //   election id (String)
+    pub fn get_election_id(&self) -> String {
+        self.election_id.clone()
+    }
+
+    // get id of the site the nonce was issued to
+    // takes:
+    //   reference to nonce of site (&SiteNonce)
+    // returns:
+    //   site id (String)
+    pub fn get_site(&self) -> String {
+        self.site.clone()
+    }
+
+    // get nonce
+    // takes:
+    //   reference to nonce of site (&SiteNonce)
+    // returns:
+    //   nonce (String)
+    pub fn get_nonce(&self) -> String {
+        self.nonce.clone()
+    }
+}
+
+// write something as pretty JSON to a file
+// the file is replaced at once, so a crash never leaves half of it for
+// the servers to read
+// takes:
+//   what to write (&T)
+//   what it is, for the messages (&str)
+//   path to JSON file (&Path)
+fn write_json<T: Serialize>(value: &T, what: &str, json_file_path: &Path) -> Result<(), Error> {
+    let json_string = serde_json::to_string_pretty(value)
+        .map_err(|e| Error::Json(format!("failed to serialize {}", what), e))?;
+
+    let tmp_path: PathBuf = json_file_path.with_extension("tmp");
+    let mut file = File::create(&tmp_path)
+        .map_err(|e| Error::Io(format!("failed to create {:?}", tmp_path), e))?;
+    file.write_all(json_string.as_bytes())
+        .map_err(|e| Error::Io(format!("failed to write JSON data to {:?}", tmp_path), e))?;
+
+    fs::rename(&tmp_path, json_file_path)
+        .map_err(|e| Error::Io(format!("failed to replace {:?}", json_file_path), e))?;
+
+    Ok(())
+}
@@ -0,0 +1,192 @@
+use sequoia_openpgp::Fingerprint;
+use serde::Deserialize;
+use ssh_key::PublicKey;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::error::Error;
+
+// an election site as registered with the servers
+// the pre-server logs sites in by their ssh key, the post-server accepts
+// results signed with their OpenPGP key
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Site {
+    site: String,
+    name: String,
+    admin: String,
+    ssh_key: String,
+    pgp_fingerprint: String,
+}
+
+impl Site {
+    // get site id
+    // takes:
+    //   reference to site (&Site)
+    // returns:
+    //   site id (String)
+    pub fn get_site(&self) -> String {
+        self.site.clone()
+    }
+
+    // get name of the site
+    // takes:
+    //   reference to site (&Site)
+    // returns:
+    //   name (String)
+    pub fn get_name(&self) -> String {
+        self.name.clone()
+    }
+
+    // get admin of the site
+    // takes:
+    //   reference to site (&Site)
+    // returns:
+    //   admin (String)
+    pub fn get_admin(&self) -> String {
+        self.admin.clone()
+    }
+
+    // check if a key is the registered ssh key of the site
+    // keys are compared without their comment, so a key is found however
+    // it was written into the registry
+    // takes:
+    //   reference to site (&Site)
+    //   key offered by client in OpenSSH format (&str)
+    // returns:
+    //   isRegisteredKey (bool)
+    pub fn has_ssh_key(&self, public_key: &str) -> bool {
+        match (
+            PublicKey::from_openssh(&self.ssh_key),
+            PublicKey::from_openssh(public_key),
+        ) {
+            (Ok(registered), Ok(offered)) => registered.key_data() == offered.key_data(),
+            _ => false,
+        }
+    }
+
+    // check if a certificate is the registered OpenPGP key of the site
+    // takes:
+    //   reference to site (&Site)
+    //   fingerprint of certificate (&Fingerprint)
+    // returns:
+    //   isRegisteredKey (bool)
+    pub fn has_fingerprint(&self, fingerprint: &Fingerprint) -> bool {
+        normalize_fingerprint(&self.pgp_fingerprint) == normalize_fingerprint(&fingerprint.to_hex())
+    }
+}
+
+// bring a fingerprint into one form, however it was written
+// takes:
+//   fingerprint (&str)
+// returns:
+//   upper case hex without spaces (String)
+fn normalize_fingerprint(fingerprint: &str) -> String {
+    fingerprint
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .to_uppercase()
+}
+
+// the election sites of an election, read by both servers from the same
+// sites.json
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Registry {
+    sites: Vec<Site>,
+}
+
+impl Registry {
+    // popoulate Registry struct from JSON file and check that every
+    // site and every key is registered only once
+    // takes:
+    //   path to JSON file (&Path)
+    // returns:
+    //   registry struct (Registry)
+    pub fn create_from_json(json_file_path: &Path) -> Result<Self, Error> {
+        let file = File::open(json_file_path).map_err(|e| {
+            Error::Io(format!("failed to open registry file {:?}", json_file_path), e)
+        })?;
+
+        let registry: Registry = serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+            Error::Json(format!("failed to parse registry {:?}", json_file_path), e)
+        })?;
+
+        let mut ssh_keys: Vec<PublicKey> = Vec::new();
+        for (i, site) in registry.sites.iter().enumerate() {
+            // site ids are used as user names and directory names
+            if site.site.is_empty()
+                || !site
+                    .site
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+            {
+                return Err(Error::Validation(format!("invalid site id: {:?}", site.site)));
+            }
+
+            let ssh_key: PublicKey = PublicKey::from_openssh(&site.ssh_key).map_err(|e| {
+                Error::Validation(format!("invalid ssh key for site {}: {}", site.site, e))
+            })?;
+
+            let fingerprint: String = normalize_fingerprint(&site.pgp_fingerprint);
+            if fingerprint.len() != 40 || !fingerprint.chars().all(|c| c.is_ascii_hexdigit()) {
+                return Err(Error::Validation(format!(
+                    "invalid OpenPGP fingerprint for site {}",
+                    site.site
+                )));
+            }
+
+            for (other, other_ssh_key) in registry.sites[..i].iter().zip(&ssh_keys) {
+                if other.site == site.site {
+                    return Err(Error::Validation(format!("site registered twice: {}", site.site)));
+                }
+                if other_ssh_key.key_data() == ssh_key.key_data() {
+                    return Err(Error::Validation(format!(
+                        "ssh key of {} also registered for {}",
+                        site.site, other.site
+                    )));
+                }
+                if normalize_fingerprint(&other.pgp_fingerprint) == fingerprint {
+                    return Err(Error::Validation(format!(
+                        "OpenPGP key of {} also registered for {}",
+                        site.site, other.site
+                    )));
+                }
+            }
+            ssh_keys.push(ssh_key);
+        }
+
+        Ok(registry)
+    }
+
+    // get all registered sites
+    // takes:
+    //   reference to registry (&Registry)
+    // returns:
+    //   reference to sites vec (&Vec<Site>)
+    pub fn get_sites(&self) -> &Vec<Site> {
+        &self.sites
+    }
+
+    // get a registered site by its id
+    // takes:
+    //   reference to registry (&Registry)
+    //   site id (&str)
+    // returns:
+    //   site, if registered (Option<&Site>)
+    pub fn get_site(&self, site: &str) -> Option<&Site> {
+        self.sites.iter().find(|s| s.site == site)
+    }
+
+    // get the site an OpenPGP certificate is registered for
+    // takes:
+    //   reference to registry (&Registry)
+    //   fingerprint of certificate (&Fingerprint)
+    // returns:
+    //   site, if registered (Option<&Site>)
+    pub fn get_by_fingerprint(&self, fingerprint: &Fingerprint) -> Option<&Site> {
+        self.sites.iter().find(|s| s.has_fingerprint(fingerprint))
+    }
+}
@@ -0,0 +1,403 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::fs::File;
+use std::io::{BufReader, ErrorKind};
+use std::path::Path;
+
+use crate::error::Error;
+
+// how seats are allocated to the votes of the parties
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Method {
+    // highest averages, divisors 1, 2, 3, ...
+    #[serde(rename = "dhondt")]
+    DHondt,
+    // highest averages, divisors 0.5, 1.5, 2.5, ... (Schepers' procedure)
+    #[serde(rename = "sainte-lague")]
+    SainteLague,
+    // largest remainders of the Hare quota
+    #[serde(rename = "hare-niemeyer")]
+    HareNiemeyer,
+}
+
+// seats to allocate for one contest
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct SeatConfig {
+    contest: String,
+    seats: u32,
+    // percent of the valid votes a party needs to get any seats, with at
+    // most two decimals
+    #[serde(default)]
+    threshold: f64,
+    method: Method,
+}
+
+impl SeatConfig {
+    // get name of contest the seats are allocated for
+    // takes:
+    //   reference to seat config (&SeatConfig)
+    // returns:
+    //   contest name (String)
+    pub fn get_contest(&self) -> String {
+        self.contest.clone()
+    }
+
+    // get number of seats
+    // takes:
+    //   reference to seat config (&SeatConfig)
+    // returns:
+    //   seats (u32)
+    pub fn get_seats(&self) -> u32 {
+        self.seats
+    }
+
+    // get threshold in percent of the valid votes
+    // takes:
+    //   reference to seat config (&SeatConfig)
+    // returns:
+    //   threshold (f64)
+    pub fn get_threshold(&self) -> f64 {
+        self.threshold
+    }
+
+    // get threshold in hundredths of a percent of the valid votes, so it
+    // can be compared without rounding
+    // takes:
+    //   reference to seat config (&SeatConfig)
+    // returns:
+    //   threshold (u32)
+    pub fn get_threshold_hundredths(&self) -> u32 {
+        (self.threshold * 100.0).round() as u32
+    }
+
+    // get allocation method
+    // takes:
+    //   reference to seat config (&SeatConfig)
+    // returns:
+    //   method (Method)
+    pub fn get_method(&self) -> Method {
+        self.method
+    }
+}
+
+// read the seat configs of all contests seats are allocated for
+// takes:
+//   path to JSON file (&Path)
+// returns:
+//   seat configs, none if there is no file (Vec<SeatConfig>)
+pub fn create_from_json(json_file_path: &Path) -> Result<Vec<SeatConfig>, Error> {
+    let file = match File::open(json_file_path) {
+        Ok(f) => f,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => {
+            return Err(Error::Io(
+                format!("failed to open seat config file {:?}", json_file_path),
+                e,
+            ))
+        }
+    };
+
+    let configs: Vec<SeatConfig> = serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+        Error::Json(format!("failed to parse seat config {:?}", json_file_path), e)
+    })?;
+    for config in &configs {
+        if !(0.0..=100.0).contains(&config.threshold) {
+            return Err(Error::Validation(format!(
+                "threshold of {} is not a percentage: {}",
+                config.contest, config.threshold
+            )));
+        }
+
+        // allocated on whole hundredths of a percent only
+        let hundredths: f64 = config.threshold * 100.0;
+        if (hundredths - hundredths.round()).abs() > 1e-6 {
+            return Err(Error::Validation(format!(
+                "threshold of {} has more than two decimals: {}",
+                config.contest, config.threshold
+            )));
+        }
+    }
+
+    Ok(configs)
+}
+
+// seats allocated to the parties
+#[derive(Debug, Clone, Serialize)]
+pub struct Allocation {
+    // seats per party, in the order the votes were given in
+    pub seats: Vec<u32>,
+    // the last seat was decided by the tie-breaking rule; the official
+    // result has to be decided by lot
+    pub tie: bool,
+}
+
+// compare two claims to a seat
+// a claim is votes / divisor, compared exactly by cross multiplying
+// takes:
+//   votes and divisor of first claim (u64, u64)
+//   votes and divisor of second claim (u64, u64)
+// returns:
+//   order of first to second claim (Ordering)
+fn compare_claims(a: (u64, u64), b: (u64, u64)) -> Ordering {
+    (a.0 as u128 * b.1 as u128).cmp(&(b.0 as u128 * a.1 as u128))
+}
+
+// find the party with the best claim to the next seat
+// ties go to the party with more votes, then to the one given first
+// takes:
+//   claims of the parties, none for parties without seats (&[Option<(u64, u64)>])
+//   votes of the parties (&[u64])
+// returns:
+//   index of party, if any party has a claim (Option<usize>)
+fn best_claim(claims: &[Option<(u64, u64)>], votes: &[u64]) -> Option<usize> {
+    let mut best: Option<usize> = None;
+
+    for (i, claim) in claims.iter().enumerate() {
+        let claim: (u64, u64) = match claim {
+            Some(c) => *c,
+            None => continue,
+        };
+
+        best = match best {
+            None => Some(i),
+            Some(b) => match compare_claims(claim, claims[b].unwrap_or((0, 1))) {
+                Ordering::Greater => Some(i),
+                Ordering::Equal if votes[i] > votes[b] => Some(i),
+                _ => Some(b),
+            },
+        };
+    }
+
+    best
+}
+
+// allocate seats by highest averages, one seat after the other to the
+// party with the highest votes / divisor
+// takes:
+//   votes of the parties that passed the threshold, 0 for the others (&[u64])
+//   seats (u32)
+//   divisor for a party's next seat, given the seats it has (fn(u32) -> u64)
+// returns:
+//   seats per party and if the last seat was a tie (Allocation)
+fn highest_averages(votes: &[u64], seats: u32, divisor: fn(u32) -> u64) -> Allocation {
+    let mut allocation = Allocation {
+        seats: vec![0; votes.len()],
+        tie: false,
+    };
+
+    let claim = |i: usize, allocated: &[u32]| -> Option<(u64, u64)> {
+        match votes[i] {
+            0 => None,
+            v => Some((v, divisor(allocated[i]))),
+        }
+    };
+
+    let mut last: Option<(u64, u64)> = None;
+    for _ in 0..seats {
+        let claims: Vec<Option<(u64, u64)>> =
+            (0..votes.len()).map(|i| claim(i, &allocation.seats)).collect();
+
+        let i: usize = match best_claim(&claims, votes) {
+            Some(i) => i,
+            None => break,
+        };
+        last = claims[i];
+        allocation.seats[i] += 1;
+    }
+
+    // a tie if a party that got no further seat had the same claim as the
+    // party that got the last one
+    if let Some(last) = last {
+        allocation.tie = (0..votes.len())
+            .filter_map(|i| claim(i, &allocation.seats))
+            .any(|c| compare_claims(c, last) == Ordering::Equal);
+    }
+
+    allocation
+}
+
+// allocate seats by largest remainders: every party gets the whole part of
+// votes * seats / valid votes, the seats left go to the largest remainders
+// takes:
+//   votes of the parties that passed the threshold, 0 for the others (&[u64])
+//   seats (u32)
+// returns:
+//   seats per party and if the last seat was a tie (Allocation)
+fn hare_niemeyer(votes: &[u64], seats: u32) -> Allocation {
+    let mut allocation = Allocation {
+        seats: vec![0; votes.len()],
+        tie: false,
+    };
+
+    let total: u128 = votes.iter().map(|v| *v as u128).sum();
+    if total == 0 {
+        return allocation;
+    }
+
+    // remainders share the denominator, so they compare as they are
+    let mut remainders: Vec<Option<(u64, u64)>> = Vec::new();
+    for (i, v) in votes.iter().enumerate() {
+        let quota: u128 = *v as u128 * seats as u128;
+        allocation.seats[i] = (quota / total) as u32;
+        remainders.push(match v {
+            0 => None,
+            _ => Some(((quota % total) as u64, 1)),
+        });
+    }
+
+    let mut left: u32 = seats - allocation.seats.iter().sum::<u32>();
+    let mut last: Option<(u64, u64)> = None;
+    while left > 0 {
+        let i: usize = match best_claim(&remainders, votes) {
+            Some(i) => i,
+            None => break,
+        };
+        last = remainders[i];
+        allocation.seats[i] += 1;
+        remainders[i] = None;
+        left -= 1;
+    }
+
+    if let Some(last) = last {
+        allocation.tie = remainders
+            .iter()
+            .flatten()
+            .any(|r| compare_claims(*r, last) == Ordering::Equal);
+    }
+
+    allocation
+}
+
+// allocate seats to the parties of a contest
+// parties below the threshold get no seats; the threshold is measured
+// against the valid votes of all parties
+// equal claims to a seat go to the party with more votes, then to the
+// one given first (the one higher up on the ballot). if that decides the
+// last seat, the allocation is flagged as a tie, since the law asks for
+// a lot then
+// the threshold is compared exactly by cross multiplying, so a party
+// with exactly the threshold passes it
+// takes:
+//   votes of the parties (&[u64])
+//   seats (u32)
+//   threshold in hundredths of a percent of the valid votes (u32)
+//   method (Method)
+// returns:
+//   seats per party, in the order of the votes (Allocation)
+pub fn allocate(votes: &[u64], seats: u32, threshold: u32, method: Method) -> Allocation {
+    let total: u128 = votes.iter().map(|v| *v as u128).sum();
+
+    // votes / total < threshold / 10000
+    let eligible: Vec<u64> = votes
+        .iter()
+        .map(|v| match total {
+            0 => 0,
+            _ if (*v as u128 * 10000) < (threshold as u128 * total) => 0,
+            _ => *v,
+        })
+        .collect();
+
+    match method {
+        Method::DHondt => highest_averages(&eligible, seats, |s| s as u64 + 1),
+        Method::SainteLague => highest_averages(&eligible, seats, |s| 2 * s as u64 + 1),
+        Method::HareNiemeyer => hare_niemeyer(&eligible, seats),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // D'Hondt method, example on Wikipedia: 230000 votes, 8 seats
+    const WIKIPEDIA_8: [u64; 4] = [100000, 80000, 30000, 20000];
+    // largest remainder method, example on Wikipedia: 100000 votes, 10 seats
+    const WIKIPEDIA_10: [u64; 6] = [47000, 16000, 15800, 12000, 6100, 3100];
+    // Sainte-Laguë method, example on Wikipedia: 100000 votes, 7 seats
+    const WIKIPEDIA_7: [u64; 3] = [53000, 24000, 23000];
+
+    #[test]
+    fn dhondt_reference() {
+        let allocation: Allocation = allocate(&WIKIPEDIA_8, 8, 0, Method::DHondt);
+        assert_eq!(allocation.seats, vec![4, 3, 1, 0]);
+        assert!(!allocation.tie);
+
+        assert_eq!(allocate(&WIKIPEDIA_10, 10, 0, Method::DHondt).seats, vec![5, 2, 2, 1, 0, 0]);
+        assert_eq!(allocate(&WIKIPEDIA_7, 7, 0, Method::DHondt).seats, vec![4, 2, 1]);
+    }
+
+    #[test]
+    fn sainte_lague_reference() {
+        let allocation: Allocation = allocate(&WIKIPEDIA_8, 8, 0, Method::SainteLague);
+        assert_eq!(allocation.seats, vec![3, 3, 1, 1]);
+        assert!(!allocation.tie);
+
+        assert_eq!(
+            allocate(&WIKIPEDIA_10, 10, 0, Method::SainteLague).seats,
+            vec![4, 2, 2, 1, 1, 0]
+        );
+        assert_eq!(allocate(&WIKIPEDIA_7, 7, 0, Method::SainteLague).seats, vec![3, 2, 2]);
+    }
+
+    #[test]
+    fn hare_niemeyer_reference() {
+        let allocation: Allocation = allocate(&WIKIPEDIA_10, 10, 0, Method::HareNiemeyer);
+        assert_eq!(allocation.seats, vec![5, 2, 1, 1, 1, 0]);
+        assert!(!allocation.tie);
+
+        assert_eq!(allocate(&WIKIPEDIA_8, 8, 0, Method::HareNiemeyer).seats, vec![3, 3, 1, 1]);
+    }
+
+    #[test]
+    fn threshold_boundary() {
+        // exactly 5 % passes
+        assert_eq!(allocate(&[500, 9500], 20, 500, Method::HareNiemeyer).seats, vec![1, 19]);
+        assert_eq!(allocate(&[7, 133], 20, 500, Method::HareNiemeyer).seats, vec![1, 19]);
+        // one vote less doesn't
+        assert_eq!(allocate(&[499, 9501], 20, 500, Method::HareNiemeyer).seats, vec![0, 20]);
+        assert_eq!(allocate(&[6, 133], 20, 500, Method::HareNiemeyer).seats, vec![0, 20]);
+        // hundredths of a percent count
+        assert_eq!(allocate(&[499, 9501], 20, 499, Method::HareNiemeyer).seats, vec![1, 19]);
+    }
+
+    #[test]
+    fn threshold_excludes_from_all_methods() {
+        let votes: [u64; 3] = [60, 36, 4];
+        for method in [Method::DHondt, Method::SainteLague, Method::HareNiemeyer] {
+            assert_eq!(allocate(&votes, 10, 500, method).seats[2], 0);
+            assert_eq!(allocate(&votes, 10, 500, method).seats.iter().sum::<u32>(), 10);
+        }
+    }
+
+    #[test]
+    fn tie_goes_to_more_votes() {
+        // second seat: 200 / 2 against 100 / 1
+        let allocation: Allocation = allocate(&[200, 100], 2, 0, Method::DHondt);
+        assert_eq!(allocation.seats, vec![2, 0]);
+        assert!(allocation.tie);
+
+        // the third seat has no tie
+        let allocation: Allocation = allocate(&[200, 100], 3, 0, Method::DHondt);
+        assert_eq!(allocation.seats, vec![2, 1]);
+        assert!(!allocation.tie);
+    }
+
+    #[test]
+    fn tie_decided_by_lot() {
+        // equal votes: the party given first gets the seat, the lot decides
+        for method in [Method::DHondt, Method::SainteLague, Method::HareNiemeyer] {
+            let allocation: Allocation = allocate(&[100, 100, 100], 2, 0, method);
+            assert_eq!(allocation.seats, vec![1, 1, 0]);
+            assert!(allocation.tie);
+        }
+    }
+
+    #[test]
+    fn no_votes() {
+        for method in [Method::DHondt, Method::SainteLague, Method::HareNiemeyer] {
+            let allocation: Allocation = allocate(&[0, 0], 5, 500, method);
+            assert_eq!(allocation.seats, vec![0, 0]);
+            assert!(!allocation.tie);
+        }
+    }
+}
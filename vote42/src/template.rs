@@ -0,0 +1,185 @@
+use serde_json::Value;
+use std::fs::File;
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::datatypes::{Vote, SCHEMA_VERSION};
+use crate::error::Error;
+
+// find names that are empty or appear more than once
+// takes:
+//   what the names are of, for the messages (&str)
+//   names (&[String])
+// returns:
+//   problems found (Vec<String>)
+pub fn check_names(what: &str, names: &[String]) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+
+    for (i, name) in names.iter().enumerate() {
+        if name.trim().is_empty() {
+            problems.push(format!("{} {} has an empty name", what, i + 1));
+        } else if names[..i].contains(name) {
+            problems.push(format!("{} listed twice: {:?}", what, name));
+        }
+    }
+
+    problems
+}
+
+// check the parts of a template that can't be checked once it is parsed,
+// because parsing would stop at the first of them
+// broken colors are reported and replaced by black, so the rest of the
+// template can still be parsed and checked
+// takes:
+//   mutable reference to parsed JSON (&mut Value)
+// returns:
+//   problems found (Vec<String>)
+fn check_raw(json: &mut Value) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+
+    match json.get("schema_version").and_then(Value::as_u64) {
+        Some(v) if v == SCHEMA_VERSION as u64 => {}
+        Some(v) => problems.push(format!(
+            "schema_version is {}, this build reads version {}",
+            v, SCHEMA_VERSION
+        )),
+        None => problems.push("schema_version is missing".to_string()),
+    };
+
+    // colors are three numbers from 0 to 255
+    let contests: &mut Vec<Value> = match json.get_mut("contests").and_then(Value::as_array_mut) {
+        Some(c) => c,
+        None => return problems,
+    };
+    for contest in contests.iter_mut() {
+        let contest_name: String = contest["name"].as_str().unwrap_or("?").to_string();
+        let parties: &mut Vec<Value> = match contest.get_mut("parties").and_then(Value::as_array_mut) {
+            Some(p) => p,
+            None => continue,
+        };
+
+        for party in parties.iter_mut() {
+            let is_color: bool = match party["color"].as_array() {
+                Some(rgb) => {
+                    rgb.len() == 3
+                        && rgb
+                            .iter()
+                            .all(|c| c.as_u64().map(|c| c <= 255).unwrap_or(false))
+                }
+                None => false,
+            };
+
+            if !is_color && party.is_object() {
+                problems.push(format!(
+                    "{}: {}: color is not [r, g, b] with values from 0 to 255: {}",
+                    contest_name,
+                    party["name"].as_str().unwrap_or("?"),
+                    party["color"]
+                ));
+                party["color"] = serde_json::json!([0, 0, 0]);
+            }
+        }
+    }
+
+    problems
+}
+
+// check the contents of a parsed template
+// takes:
+//   reference to vote template (&Vote)
+// returns:
+//   problems found (Vec<String>)
+fn check_vote(vote: &Vote) -> Vec<String> {
+    let mut problems: Vec<String> = Vec::new();
+
+    if vote.get_election_id().trim().is_empty() {
+        problems.push("election_id is empty".to_string());
+    }
+
+    // the nonce is issued to every site on its own, never in the template
+    if !vote.get_nonce().is_empty() {
+        problems.push("template contains a nonce".to_string());
+    }
+
+    if vote.get_contests().is_empty() {
+        problems.push("template contains no contests".to_string());
+    }
+
+    let contest_names: Vec<String> = vote.get_contests().iter().map(|c| c.get_name()).collect();
+    problems.append(&mut check_names("contest", &contest_names));
+
+    for contest in vote.get_contests() {
+        // the same checks every result has to pass
+        let mut contest_problems: Vec<String> = contest.check_options();
+
+        if contest.get_votes_per_ballot() == 0 {
+            contest_problems.push("votes_per_ballot is 0".to_string());
+        }
+
+        for party in contest.get_parties() {
+            if party.get_long_name().trim().is_empty() {
+                contest_problems.push(format!("{}: long_name is empty", party.get_name()));
+            }
+
+            if party.get_votes() != 0 {
+                contest_problems.push(format!("{}: votes are not 0", party.get_name()));
+            }
+
+            if let Some(share) = party.get_previous_share() {
+                if !(0.0..=100.0).contains(&share) {
+                    contest_problems.push(format!(
+                        "{}: previous_share is not a percentage: {}",
+                        party.get_name(),
+                        share
+                    ));
+                }
+            }
+
+            if contest.has_candidate_votes() && party.get_candidates().is_empty() {
+                contest_problems.push(format!(
+                    "{}: votes are counted per candidate, but there are no candidates",
+                    party.get_name()
+                ));
+            }
+
+            let candidate_names: Vec<String> =
+                party.get_candidates().iter().map(|c| c.get_name()).collect();
+            for problem in check_names("candidate", &candidate_names) {
+                contest_problems.push(format!("{}: {}", party.get_name(), problem));
+            }
+        }
+
+        for problem in contest_problems {
+            problems.push(format!("{}: {}", contest.get_name(), problem));
+        }
+    }
+
+    problems
+}
+
+// check a vote template and report all problems at once, so a template
+// can be fixed before it is handed out
+// takes:
+//   path to template (&Path)
+// returns:
+//   problems found, none if the template is fine (Vec<String>)
+pub fn check_template(json_file_path: &Path) -> Result<Vec<String>, Error> {
+    println!("checking vote template: {:?}", json_file_path);
+
+    let file = File::open(json_file_path).map_err(|e| {
+        Error::Io(format!("failed to open vote template file {:?}", json_file_path), e)
+    })?;
+    let mut json: Value = serde_json::from_reader(BufReader::new(file)).map_err(|e| {
+        Error::Json(format!("vote template {:?} is not JSON", json_file_path), e)
+    })?;
+
+    let mut problems: Vec<String> = check_raw(&mut json);
+
+    // unknown fields, missing fields and wrong types
+    match serde_json::from_value::<Vote>(json) {
+        Ok(vote) => problems.append(&mut check_vote(&vote)),
+        Err(e) => problems.push(format!("template does not match the schema: {}", e)),
+    };
+
+    Ok(problems)
+}
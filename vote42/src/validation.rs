@@ -0,0 +1,156 @@
+use crate::datatypes::{Contest, Vote};
+
+const MAX_SHARE_DEVIATION: f64 = 15.0; // percentage points a party's share may differ from the previous election
+
+// what the plausibility checks found
+// errors mean the counts can't be right, warnings that someone should
+// have another look at them
+#[derive(Debug, Default)]
+pub struct Report {
+    pub errors: Vec<String>,
+    pub warnings: Vec<String>,
+}
+
+// check that the counts of every contest of a vote are plausible
+// takes:
+//   reference to vote (&Vote)
+// returns:
+//   errors and warnings found, prefixed with their contest (Report)
+pub fn check_vote(vote: &Vote) -> Report {
+    let mut report = Report::default();
+
+    for contest in vote.get_contests().iter() {
+        let contest_report: Report = check_contest(contest);
+
+        for error in contest_report.errors {
+            report.errors.push(format!("{}: {}", contest.get_name(), error));
+        }
+        for warning in contest_report.warnings {
+            report.warnings.push(format!("{}: {}", contest.get_name(), warning));
+        }
+    }
+
+    report
+}
+
+// check that the counts of a contest are plausible
+// takes:
+//   reference to contest (&Contest)
+// returns:
+//   errors and warnings found (Report)
+fn check_contest(contest: &Contest) -> Report {
+    let mut report = Report::default();
+
+    // summed up as u64 so no count can overflow
+    let valid_votes: u64 = contest.get_parties().iter().map(|p| p.get_votes() as u64).sum();
+
+    // CANDIDATES
+    if contest.has_candidate_votes() {
+        for party in contest.get_parties() {
+            let candidate_votes: u64 = party
+                .get_candidates()
+                .iter()
+                .map(|c| c.get_votes() as u64)
+                .sum();
+
+            if candidate_votes != party.get_votes() as u64 {
+                report.errors.push(format!(
+                    "{} has {} votes, but its candidates have {}",
+                    party.get_name(),
+                    party.get_votes(),
+                    candidate_votes
+                ));
+            }
+        }
+    }
+
+    // ELECTORATE
+    // ballots cast = invalid ballots + blank ballots + ballots with party votes
+    // every ballot with party votes has up to votes_per_ballot votes
+    let votes_per_ballot: u64 = contest.get_votes_per_ballot() as u64;
+    if votes_per_ballot == 0 {
+        report.errors.push("votes_per_ballot is 0".to_string());
+    }
+
+    match contest.get_electorate() {
+        Some(electorate) => {
+            let registered: u64 = electorate.get_registered_voters() as u64;
+            let voters: u64 = electorate.get_voters() as u64;
+            let cast: u64 = electorate.get_ballots_cast() as u64;
+            let invalid: u64 = electorate.get_invalid_ballots() as u64;
+            let blank: u64 = electorate.get_blank_ballots() as u64;
+
+            if voters > registered {
+                report.errors.push(format!(
+                    "turnout above 100%: {} voters, {} registered voters",
+                    voters, registered
+                ));
+            }
+
+            if cast > registered {
+                report.errors.push(format!(
+                    "more ballots cast ({}) than registered voters ({})",
+                    cast, registered
+                ));
+            }
+
+            // voters may take their ballot home, but never put in two
+            if cast > voters {
+                report.errors.push(format!(
+                    "more ballots cast ({}) than voters ({})",
+                    cast, voters
+                ));
+            } else if cast < voters {
+                report.warnings.push(format!(
+                    "fewer ballots cast ({}) than voters ({})",
+                    cast, voters
+                ));
+            }
+
+            if invalid + blank > cast {
+                report.errors.push(format!(
+                    "more invalid ({}) and blank ({}) ballots than ballots cast ({})",
+                    invalid, blank, cast
+                ));
+            } else if valid_votes > (cast - invalid - blank) * votes_per_ballot {
+                report.errors.push(format!(
+                    "party votes ({}) exceed the votes on the ballots cast for a party ({})",
+                    valid_votes,
+                    (cast - invalid - blank) * votes_per_ballot
+                ));
+            } else if votes_per_ballot == 1 && valid_votes < cast - invalid - blank {
+                // with more votes per ballot not all of them have to be used
+                report.warnings.push(format!(
+                    "party votes ({}) are fewer than the ballots cast for a party ({})",
+                    valid_votes,
+                    cast - invalid - blank
+                ));
+            }
+        }
+        None => report
+            .warnings
+            .push("no electorate data, turnout and ballots can't be checked".to_string()),
+    };
+
+    // PREVIOUS ELECTION
+    if valid_votes > 0 {
+        for party in contest.get_parties() {
+            let previous: f64 = match party.get_previous_share() {
+                Some(s) => s,
+                None => continue,
+            };
+
+            let share: f64 = party.get_votes() as f64 / valid_votes as f64 * 100.0;
+            if (share - previous).abs() > MAX_SHARE_DEVIATION {
+                report.warnings.push(format!(
+                    "{} got {:.1}% of the votes, {:.1}% in the previous election",
+                    party.get_name(),
+                    share,
+                    previous
+                ));
+            }
+        }
+    }
+
+    report
+}